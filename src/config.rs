@@ -1,11 +1,21 @@
+use crate::types::Status;
 use anyhow::{Context, Result};
+use chrono::{NaiveTime, Weekday};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct Config {
     pub global: GlobalConfig,
+    #[serde(default)]
     pub monitors: Vec<MonitorConfig>,
+    #[serde(default)]
+    pub discover: Vec<DiscoverConfig>,
+    #[serde(default)]
+    pub notifiers: Vec<NotifierConfig>,
+    #[serde(default)]
+    pub routes: Vec<RouteConfig>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -14,8 +24,161 @@ pub struct GlobalConfig {
     pub warning_threshold: u64,
     pub critical_threshold: u64,
     pub webhook_url: Option<String>,
+    #[serde(default)]
+    pub webhook_recovery: bool,
+    #[serde(default)]
+    pub webhook_auth: WebhookAuthConfig,
     pub metrics_enabled: Option<bool>,
     pub metrics_addr: Option<String>,
+    pub api_token: Option<String>,
+    pub tls: Option<TlsConfig>,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    pub state_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    #[serde(default)]
+    pub repeat: RepeatConfig,
+    #[serde(default)]
+    pub milestones: Vec<u64>,
+    #[serde(default)]
+    pub alert_on_error: bool,
+    #[serde(default)]
+    pub confirm: ConfirmConfig,
+    pub flap: Option<FlapConfig>,
+    pub digest: Option<DigestConfig>,
+    pub group: Option<GroupConfig>,
+    pub otel: Option<OtelConfig>,
+    pub push: Option<PushConfig>,
+    #[serde(default)]
+    pub health: HealthConfig,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TlsConfig {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+    #[serde(default = "default_tls_reload_interval")]
+    pub reload_interval: u64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct AuthConfig {
+    pub metrics: Option<HttpAuth>,
+    pub api: Option<HttpAuth>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum HttpAuth {
+    Bearer { token: String },
+    Basic { username: String, password: String },
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct HealthConfig {
+    pub live_after: Option<u64>,
+    pub ready_after: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PushConfig {
+    pub gateway: Option<String>,
+    pub textfile: Option<PathBuf>,
+    #[serde(default = "default_push_job")]
+    pub job: String,
+    pub instance: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OtelConfig {
+    pub endpoint: Option<String>,
+    #[serde(default)]
+    pub protocol: OtelProtocol,
+    pub service_name: Option<String>,
+    #[serde(default = "default_true")]
+    pub metrics: bool,
+    #[serde(default = "default_true")]
+    pub traces: bool,
+    #[serde(default = "default_otel_interval")]
+    pub interval: u64,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OtelProtocol {
+    #[default]
+    Grpc,
+    Http,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GroupConfig {
+    #[serde(default)]
+    pub by: GroupBy,
+    #[serde(default = "default_group_max")]
+    pub max: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBy {
+    #[default]
+    Chain,
+    Reason,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DigestConfig {
+    #[serde(default)]
+    pub schedule: DigestSchedule,
+    #[serde(default = "default_digest_at")]
+    pub at: NaiveTime,
+    pub weekday: Option<Weekday>,
+    #[serde(default = "default_upcoming_days")]
+    pub upcoming_days: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestSchedule {
+    #[default]
+    Daily,
+    Weekly,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ConfirmConfig {
+    pub healthy: Option<u32>,
+    pub warning: Option<u32>,
+    pub critical: Option<u32>,
+    pub expired: Option<u32>,
+    pub error: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FlapConfig {
+    pub window: u64,
+    pub max_transitions: usize,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RepeatConfig {
+    pub warning: Option<u64>,
+    pub critical: Option<u64>,
+    pub expired: Option<u64>,
+    pub error: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RetryConfig {
+    pub attempts: u32,
+    pub initial_backoff: u64,
+    pub max_backoff: u64,
+    pub max_age: u64,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -29,6 +192,159 @@ pub struct MonitorConfig {
     pub channel: String,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DiscoverConfig {
+    pub chain_id: String,
+    pub rpc_addr: String,
+    pub grpc_addr: String,
+    #[serde(default)]
+    pub include: DiscoverFilter,
+    #[serde(default)]
+    pub exclude: DiscoverFilter,
+    #[serde(default = "default_discover_refresh")]
+    pub refresh_interval: u64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct DiscoverFilter {
+    #[serde(default)]
+    pub counterparty: Vec<String>,
+    #[serde(default)]
+    pub channel_state: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RouteConfig {
+    #[serde(default)]
+    pub severity: Vec<String>,
+    pub chain_id: Option<String>,
+    pub counterparty: Option<String>,
+    pub name: Option<String>,
+    pub notifiers: Vec<String>,
+    #[serde(default)]
+    pub default: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NotifierConfig {
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub recovery: bool,
+    #[serde(default = "default_true")]
+    pub digest: bool,
+    #[serde(flatten)]
+    pub kind: NotifierKind,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotifierKind {
+    Slack(SlackConfig),
+    Matrix(MatrixConfig),
+    Email(EmailConfig),
+    Template(TemplateConfig),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SlackConfig {
+    pub url: String,
+    #[serde(flatten)]
+    pub auth: WebhookAuthConfig,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct WebhookAuthConfig {
+    pub secret: Option<String>,
+    pub replay_window: Option<u64>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MatrixConfig {
+    pub homeserver: String,
+    pub room_id: String,
+    pub access_token: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EmailConfig {
+    pub host: String,
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub tls: SmtpTls,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    pub subject: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TemplateConfig {
+    pub url: String,
+    pub method: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+    pub digest_body: Option<String>,
+    pub group_body: Option<String>,
+    pub secret: Option<String>,
+    pub replay_window: Option<u64>,
+    #[serde(default)]
+    pub escape: TemplateEscape,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateEscape {
+    #[default]
+    Json,
+    Html,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpTls {
+    #[default]
+    Starttls,
+    Tls,
+    None,
+}
+
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_digest_at() -> NaiveTime {
+    NaiveTime::from_hms_opt(9, 0, 0).unwrap()
+}
+
+fn default_upcoming_days() -> u64 {
+    7
+}
+
+fn default_group_max() -> usize {
+    20
+}
+
+fn default_otel_interval() -> u64 {
+    60
+}
+
+fn default_push_job() -> String {
+    "ibc-monitor".to_string()
+}
+
+fn default_tls_reload_interval() -> u64 {
+    60
+}
+
+fn default_discover_refresh() -> u64 {
+    3600
+}
 
 impl Config {
     pub fn load(path: &Path) -> Result<Self> {
@@ -44,8 +360,84 @@ impl Default for GlobalConfig {
             warning_threshold: 48,
             critical_threshold: 24,
             webhook_url: None,
+            webhook_recovery: false,
+            webhook_auth: WebhookAuthConfig::default(),
             metrics_enabled: Some(true),
             metrics_addr: Some("0.0.0.0:9090".to_string()),
+            api_token: None,
+            tls: None,
+            auth: AuthConfig::default(),
+            state_dir: None,
+            retry: RetryConfig::default(),
+            repeat: RepeatConfig::default(),
+            milestones: Vec::new(),
+            alert_on_error: false,
+            confirm: ConfirmConfig::default(),
+            flap: None,
+            digest: None,
+            group: None,
+            otel: None,
+            push: None,
+            health: HealthConfig::default(),
+        }
+    }
+}
+
+impl RepeatConfig {
+    pub fn interval(&self, status: &Status) -> Option<chrono::Duration> {
+        let hours = match status {
+            Status::Healthy { .. } => None,
+            Status::Warning { .. } => self.warning,
+            Status::Critical { .. } => self.critical,
+            Status::Expired { .. } => self.expired,
+            Status::Error { .. } => self.error,
+        }?;
+        Some(chrono::Duration::hours(hours as i64))
+    }
+}
+
+impl HealthConfig {
+    // a sweep is expected every check_interval, allow a few to run long before failing probes
+    pub fn live_after(&self, check_interval: u64) -> chrono::Duration {
+        chrono::Duration::seconds(self.live_after.unwrap_or(check_interval * 3) as i64)
+    }
+
+    pub fn ready_after(&self, check_interval: u64) -> chrono::Duration {
+        chrono::Duration::seconds(self.ready_after.unwrap_or(check_interval * 3) as i64)
+    }
+}
+
+impl GlobalConfig {
+    // credentials for mutating routes: api_token when set, so `trigger` keeps working
+    // behind basic auth, else whatever guards the read-only api
+    pub fn admin_auth(&self) -> Option<HttpAuth> {
+        match &self.api_token {
+            Some(token) => Some(HttpAuth::Bearer { token: token.clone() }),
+            None => self.auth.api.clone(),
+        }
+    }
+}
+
+impl ConfirmConfig {
+    pub fn required(&self, status: &Status) -> u32 {
+        match status {
+            Status::Healthy { .. } => self.healthy,
+            Status::Warning { .. } => self.warning,
+            Status::Critical { .. } => self.critical,
+            Status::Expired { .. } => self.expired,
+            Status::Error { .. } => self.error,
+        }
+        .unwrap_or(1)
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            initial_backoff: 1,
+            max_backoff: 30,
+            max_age: 24,
         }
     }
 }
\ No newline at end of file
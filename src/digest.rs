@@ -0,0 +1,200 @@
+use crate::config::{DigestConfig, DigestSchedule};
+use crate::notify::{escape_html, Message};
+use crate::types::{CheckResult, ClientStatus, Status, Summary};
+use chrono::{DateTime, Datelike, Duration, Utc, Weekday};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Digest {
+    pub schedule: DigestSchedule,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub upcoming_days: u64,
+    pub summary: Summary,
+    pub clients: Vec<ClientStatus>,
+    pub upcoming: Vec<ClientStatus>,
+    pub errored: Vec<ErroredClient>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErroredClient {
+    pub chain_id: String,
+    pub client_id: String,
+    pub channel: String,
+    pub count: u32,
+    pub last_reason: String,
+}
+
+pub struct DigestScheduler {
+    cfg: DigestConfig,
+    start: DateTime<Utc>,
+    next: DateTime<Utc>,
+    errored: BTreeMap<(String, String), ErroredClient>,
+}
+
+impl DigestScheduler {
+    pub fn new(cfg: DigestConfig) -> Self {
+        let now = Utc::now();
+        Self {
+            next: next_run(&cfg, now),
+            start: now,
+            cfg,
+            errored: BTreeMap::new(),
+        }
+    }
+
+    // records errors from every sweep and returns a digest once the schedule is due
+    pub fn observe(&mut self, result: &CheckResult) -> Option<Digest> {
+        for client in result.monitors.iter().flat_map(|m| &m.clients) {
+            if let Status::Error { reason } = &client.status {
+                let e = self
+                    .errored
+                    .entry((client.chain_id.clone(), client.channel.clone()))
+                    .or_insert_with(|| ErroredClient {
+                        chain_id: client.chain_id.clone(),
+                        client_id: client.client_id.clone(),
+                        channel: client.channel.clone(),
+                        count: 0,
+                        last_reason: String::new(),
+                    });
+                e.count += 1;
+                e.last_reason = reason.clone();
+            }
+        }
+
+        if result.timestamp < self.next {
+            return None;
+        }
+
+        let mut clients: Vec<_> = result.monitors.iter().flat_map(|m| m.clients.clone()).collect();
+        clients.sort_by(|a, b| expiry_key(a).total_cmp(&expiry_key(b)));
+        let horizon = (self.cfg.upcoming_days * 24) as f64;
+        let upcoming = clients
+            .iter()
+            .filter(|c| !matches!(c.status, Status::Error { .. }) && expiry_key(c) < horizon)
+            .cloned()
+            .collect();
+
+        let digest = Digest {
+            schedule: self.cfg.schedule,
+            start: self.start,
+            end: result.timestamp,
+            upcoming_days: self.cfg.upcoming_days,
+            summary: result.summary.clone(),
+            clients,
+            upcoming,
+            errored: std::mem::take(&mut self.errored).into_values().collect(),
+        };
+        self.start = result.timestamp;
+        self.next = next_run(&self.cfg, result.timestamp);
+        Some(digest)
+    }
+}
+
+impl Digest {
+    pub fn title(&self) -> String {
+        let period = match self.schedule {
+            DigestSchedule::Daily => "daily",
+            DigestSchedule::Weekly => "weekly",
+        };
+        format!("📋 ibc {} digest - {}", period, self.end.format("%Y-%m-%d"))
+    }
+
+    fn sections(&self) -> Vec<(String, Vec<String>)> {
+        let s = &self.summary;
+        let mut sections = vec![(
+            format!(
+                "{} - {} UTC",
+                self.start.format("%Y-%m-%d %H:%M"),
+                self.end.format("%Y-%m-%d %H:%M")
+            ),
+            vec![format!(
+                "{} total | {} healthy | {} warning | {} critical | {} expired | {} errors",
+                s.total, s.healthy, s.warning, s.critical, s.expired, s.error
+            )],
+        )];
+
+        sections.push((
+            format!("upcoming expiries (next {} days)", self.upcoming_days),
+            self.upcoming.iter().map(client_line).collect(),
+        ));
+        sections.push((
+            "errored during period".to_string(),
+            self.errored
+                .iter()
+                .map(|e| {
+                    format!(
+                        "{} {} ({}): {} failed checks, last: {}",
+                        e.chain_id, e.client_id, e.channel, e.count, e.last_reason
+                    )
+                })
+                .collect(),
+        ));
+        sections.push((
+            "all clients by time until expiry".to_string(),
+            self.clients.iter().map(client_line).collect(),
+        ));
+        sections
+    }
+
+    pub fn message(&self) -> Message {
+        let sections = self.sections();
+        let text = sections
+            .iter()
+            .map(|(heading, lines)| {
+                let lines = if lines.is_empty() { vec!["none".to_string()] } else { lines.clone() };
+                format!("{}\n{}", heading, lines.iter().map(|l| format!("  {}", l)).collect::<Vec<_>>().join("\n"))
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let html = sections
+            .iter()
+            .map(|(heading, lines)| {
+                let items = if lines.is_empty() {
+                    "<li>none</li>".to_string()
+                } else {
+                    lines.iter().map(|l| format!("<li>{}</li>", escape_html(l))).collect()
+                };
+                format!("<h4>{}</h4><ul>{}</ul>", escape_html(heading), items)
+            })
+            .collect();
+
+        Message { title: self.title(), text, html }
+    }
+}
+
+pub fn client_line(c: &ClientStatus) -> String {
+    format!(
+        "{} {} {} ({}) -> {}: {}",
+        c.status.emoji(),
+        c.chain_id,
+        c.client_id,
+        c.channel,
+        c.counterparty_chain_id.as_deref().unwrap_or("?"),
+        c.status.describe()
+    )
+}
+
+// hours until expiry, with expired clients first and errors last
+fn expiry_key(c: &ClientStatus) -> f64 {
+    match &c.status {
+        Status::Healthy { hours_until_expiry: h }
+        | Status::Warning { hours_until_expiry: h }
+        | Status::Critical { hours_until_expiry: h } => *h,
+        Status::Expired { hours_since_expiry: h } => -*h,
+        Status::Error { .. } => f64::MAX,
+    }
+}
+
+fn next_run(cfg: &DigestConfig, after: DateTime<Utc>) -> DateTime<Utc> {
+    let weekday = match cfg.schedule {
+        DigestSchedule::Daily => None,
+        DigestSchedule::Weekly => Some(cfg.weekday.unwrap_or(Weekday::Mon)),
+    };
+    let mut next = after.date_naive().and_time(cfg.at).and_utc();
+    while next <= after || weekday.is_some_and(|d| next.weekday() != d) {
+        next += Duration::days(1);
+    }
+    next
+}
@@ -0,0 +1,268 @@
+use crate::config::{DiscoverConfig, MonitorConfig};
+use crate::monitor::{connect, consensus_timestamp, endpoint_host, parse_tendermint_client_state, rpc};
+use crate::silence::glob_match;
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use ibc_proto::cosmos::base::query::v1beta1::{PageRequest, PageResponse};
+use ibc_proto::ibc::core::{
+    channel::v1::{query_client::QueryClient as ChannelQueryClient, QueryChannelsRequest, State},
+    client::v1::{query_client::QueryClient, QueryClientStatesRequest},
+    connection::v1::{query_client::QueryClient as ConnectionQueryClient, QueryConnectionsRequest},
+};
+use ibc_proto::ibc::lightclients::tendermint::v1::ClientState as TendermintClientState;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+const PAGE_LIMIT: u64 = 500;
+const CONCURRENT_QUERIES: usize = 8;
+const CHANNEL_STATES: [&str; 7] = ["uninitialized", "init", "tryopen", "open", "closed", "flushing", "flushcomplete"];
+
+#[derive(Debug, Clone)]
+pub struct DiscoveredChannel {
+    pub port_id: String,
+    pub channel_id: String,
+    pub connection_id: String,
+    pub state: &'static str,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiscoveredClient {
+    pub client_id: String,
+    pub client_state: TendermintClientState,
+    pub connections: Vec<String>,
+    pub channels: Vec<DiscoveredChannel>,
+}
+
+// every tendermint client on the chain with the channels opened over its connections
+pub async fn discover(grpc_addr: &str) -> Result<Vec<DiscoveredClient>> {
+    let endpoint = endpoint_host(grpc_addr);
+    let channel = connect(grpc_addr).await?;
+
+    let client_states = paged(|pagination| {
+        let mut client = QueryClient::new(channel.clone());
+        let endpoint = endpoint.clone();
+        async move {
+            let r = rpc(&endpoint, "client_states", client.client_states(QueryClientStatesRequest { pagination })).await?;
+            Ok((r.client_states, r.pagination))
+        }
+    })
+    .await?;
+    let connections = paged(|pagination| {
+        let mut client = ConnectionQueryClient::new(channel.clone());
+        let endpoint = endpoint.clone();
+        async move {
+            let r = rpc(&endpoint, "connections", client.connections(QueryConnectionsRequest { pagination })).await?;
+            Ok((r.connections, r.pagination))
+        }
+    })
+    .await?;
+    let channels = paged(|pagination| {
+        let mut client = ChannelQueryClient::new(channel.clone());
+        let endpoint = endpoint.clone();
+        async move {
+            let r = rpc(&endpoint, "channels", client.channels(QueryChannelsRequest { pagination })).await?;
+            Ok((r.channels, r.pagination))
+        }
+    })
+    .await?;
+
+    // other light client types (solo machines, localhost, wasm) have no trusting period to watch
+    let mut clients: BTreeMap<String, DiscoveredClient> = client_states
+        .into_iter()
+        .filter_map(|c| {
+            let client_state = parse_tendermint_client_state(c.client_state.as_ref()?).ok()?;
+            Some((
+                c.client_id.clone(),
+                DiscoveredClient {
+                    client_id: c.client_id,
+                    client_state,
+                    connections: Vec::new(),
+                    channels: Vec::new(),
+                },
+            ))
+        })
+        .collect();
+
+    let mut connection_clients = BTreeMap::new();
+    for conn in connections {
+        if let Some(client) = clients.get_mut(&conn.client_id) {
+            client.connections.push(conn.id.clone());
+            connection_clients.insert(conn.id, conn.client_id);
+        }
+    }
+    for chan in channels {
+        let Some(connection_id) = chan.connection_hops.first() else { continue };
+        let Some(client) = connection_clients.get(connection_id).and_then(|id| clients.get_mut(id)) else {
+            continue;
+        };
+        client.channels.push(DiscoveredChannel {
+            port_id: chan.port_id,
+            channel_id: chan.channel_id,
+            connection_id: connection_id.clone(),
+            state: channel_state(chan.state),
+        });
+    }
+    Ok(clients.into_values().collect())
+}
+
+// last update of each client, queried a few at a time to go easy on public endpoints
+pub async fn last_updates(grpc_addr: &str, clients: &[DiscoveredClient]) -> Result<Vec<Result<DateTime<Utc>>>> {
+    let endpoint = endpoint_host(grpc_addr);
+    let channel = connect(grpc_addr).await?;
+    let mut updates = Vec::with_capacity(clients.len());
+    for chunk in clients.chunks(CONCURRENT_QUERIES) {
+        let mut tasks = JoinSet::new();
+        for (i, c) in chunk.iter().enumerate() {
+            let mut client = QueryClient::new(channel.clone());
+            let (endpoint, client_id, height) = (endpoint.clone(), c.client_id.clone(), c.client_state.latest_height);
+            tasks.spawn(async move {
+                let update = match height {
+                    Some(height) => consensus_timestamp(&mut client, &endpoint, &client_id, height).await,
+                    None => Err(anyhow!("no latest height")),
+                };
+                (i, update)
+            });
+        }
+        let mut results: Vec<_> = tasks.join_all().await;
+        results.sort_by_key(|(i, _)| *i);
+        updates.extend(results.into_iter().map(|(_, r)| r));
+    }
+    Ok(updates)
+}
+
+fn channel_state(state: i32) -> &'static str {
+    match State::try_from(state) {
+        Ok(State::UninitializedUnspecified) | Err(_) => "uninitialized",
+        Ok(State::Init) => "init",
+        Ok(State::Tryopen) => "tryopen",
+        Ok(State::Open) => "open",
+        Ok(State::Closed) => "closed",
+        Ok(State::Flushing) => "flushing",
+        Ok(State::Flushcomplete) => "flushcomplete",
+    }
+}
+
+async fn paged<T, F, Fut>(mut fetch: F) -> Result<Vec<T>>
+where
+    F: FnMut(Option<PageRequest>) -> Fut,
+    Fut: Future<Output = Result<(Vec<T>, Option<PageResponse>)>>,
+{
+    let mut items = Vec::new();
+    let mut key = Vec::new();
+    loop {
+        let (page, next) = fetch(Some(PageRequest {
+            key,
+            limit: PAGE_LIMIT,
+            ..Default::default()
+        }))
+        .await?;
+        items.extend(page);
+        match next {
+            Some(next) if !next.next_key.is_empty() => key = next.next_key,
+            _ => return Ok(items),
+        }
+    }
+}
+
+// one monitor per client with a channel passing the filters, checked through its first such channel
+pub fn monitors(cfg: &DiscoverConfig, clients: &[DiscoveredClient]) -> Vec<MonitorConfig> {
+    let state_included = |s: &str| match cfg.include.channel_state.is_empty() {
+        true => s == "open",
+        false => cfg.include.channel_state.iter().any(|i| i == s),
+    };
+
+    clients
+        .iter()
+        .filter(|c| counterparty_included(cfg, &c.client_state.chain_id))
+        .filter_map(|c| {
+            let mut channels: Vec<_> = c
+                .channels
+                .iter()
+                .filter(|ch| state_included(ch.state) && !cfg.exclude.channel_state.iter().any(|e| e == ch.state))
+                .collect();
+            channels.sort_by_key(|ch| ch.port_id != "transfer");
+            let first = channels.first()?;
+            Some(MonitorConfig {
+                name: format!("{} on {} ({})", c.client_state.chain_id, cfg.chain_id, c.client_id),
+                description: format!(
+                    "discovered, channels {}",
+                    channels.iter().map(|ch| format!("{}/{}", ch.port_id, ch.channel_id)).collect::<Vec<_>>().join(", ")
+                ),
+                chain_id: cfg.chain_id.clone(),
+                rpc_addr: cfg.rpc_addr.clone(),
+                grpc_addr: cfg.grpc_addr.clone(),
+                client_id: Some(c.client_id.clone()),
+                channel: first.channel_id.clone(),
+            })
+        })
+        .collect()
+}
+
+pub fn counterparty_included(cfg: &DiscoverConfig, counterparty: &str) -> bool {
+    let matches = |patterns: &[String]| patterns.iter().any(|p| glob_match(p, counterparty));
+    (cfg.include.counterparty.is_empty() || matches(&cfg.include.counterparty)) && !matches(&cfg.exclude.counterparty)
+}
+
+pub fn validate(cfg: &DiscoverConfig) -> Result<()> {
+    let mut states = cfg.include.channel_state.iter().chain(&cfg.exclude.channel_state);
+    if let Some(s) = states.find(|s| !CHANNEL_STATES.contains(&s.as_str())) {
+        bail!("discover {}: unknown channel state {}", cfg.chain_id, s);
+    }
+    Ok(())
+}
+
+struct Source {
+    cfg: DiscoverConfig,
+    refreshed: Option<Instant>,
+    monitors: Vec<MonitorConfig>,
+}
+
+// discovered monitors per [[discover]] entry, refreshed every refresh_interval
+pub struct Discovery(Mutex<Vec<Source>>);
+
+impl Discovery {
+    pub fn new(configs: Vec<DiscoverConfig>) -> Result<Self> {
+        configs.iter().try_for_each(validate)?;
+        Ok(Self(Mutex::new(
+            configs
+                .into_iter()
+                .map(|cfg| Source {
+                    cfg,
+                    refreshed: None,
+                    monitors: Vec::new(),
+                })
+                .collect(),
+        )))
+    }
+
+    pub async fn monitors(&self) -> Vec<MonitorConfig> {
+        let mut sources = self.0.lock().await;
+        for source in sources.iter_mut() {
+            let due = source
+                .refreshed
+                .is_none_or(|t| t.elapsed() >= Duration::from_secs(source.cfg.refresh_interval));
+            if !due {
+                continue;
+            }
+            // a failed refresh keeps the previous monitors and is retried on the next sweep
+            match discover(&source.cfg.grpc_addr).await {
+                Ok(clients) => {
+                    source.monitors = monitors(&source.cfg, &clients);
+                    source.refreshed = Some(Instant::now());
+                    info!(
+                        chain = %source.cfg.chain_id,
+                        clients = clients.len(),
+                        monitors = source.monitors.len(),
+                        "discovered clients"
+                    );
+                }
+                Err(e) => warn!(chain = %source.cfg.chain_id, "client discovery failed: {:#}", e),
+            }
+        }
+        sources.iter().flat_map(|s| s.monitors.clone()).collect()
+    }
+}
@@ -0,0 +1,115 @@
+use crate::config::{EmailConfig, SmtpTls};
+use crate::notify::{escape_html, Alert, Message};
+use anyhow::{Context, Result};
+use handlebars::Handlebars;
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+use std::sync::Arc;
+use std::time::Duration;
+
+const DEFAULT_SUBJECT: &str = "[ibc-monitor] {{status}}: {{client_id}} on {{chain_id}}";
+
+#[derive(Clone)]
+pub struct EmailClient {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    templates: Arc<Handlebars<'static>>,
+}
+
+impl EmailClient {
+    pub fn new(cfg: &EmailConfig) -> Result<Self> {
+        let mut builder = match cfg.tls {
+            SmtpTls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&cfg.host)?,
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&cfg.host)?,
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&cfg.host),
+        }
+        .timeout(Some(Duration::from_secs(10)));
+
+        if let Some(port) = cfg.port {
+            builder = builder.port(port);
+        }
+        if let (Some(user), Some(pass)) = (&cfg.username, &cfg.password) {
+            builder = builder.credentials(Credentials::new(user.clone(), pass.clone()));
+        }
+
+        let mut templates = Handlebars::new();
+        templates.register_escape_fn(handlebars::no_escape);
+        templates
+            .register_template_string("subject", cfg.subject.as_deref().unwrap_or(DEFAULT_SUBJECT))
+            .context("invalid email subject template")?;
+
+        Ok(Self {
+            transport: builder.build(),
+            from: cfg.from.parse().context("invalid email from address")?,
+            to: cfg
+                .to
+                .iter()
+                .map(|a| a.parse().with_context(|| format!("invalid email to address: {}", a)))
+                .collect::<Result<_>>()?,
+            templates: Arc::new(templates),
+        })
+    }
+
+    pub async fn send_alert(&self, alert: &Alert) -> Result<()> {
+        let c = &alert.client;
+        let label = c.status.label();
+        let (title, mut rows) = (alert.title(), alert.fields());
+
+        let subject = self.templates.render("subject", &alert.context())?;
+
+        rows.insert(0, ("monitor", alert.monitor.name.clone()));
+        rows.push(("channel", c.channel.clone()));
+        rows.push((
+            "last update",
+            c.last_update
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+                .unwrap_or_else(|| "unknown".to_string()),
+        ));
+        if !rows.iter().any(|(k, _)| *k == "expires at") {
+            rows.push(("expires at", alert.expires_at().unwrap_or_else(|| "unknown".to_string())));
+        }
+        rows.push((
+            "trust/ub",
+            format!("{}d/{}d", c.trusting_period.num_days(), c.unbonding_period.num_days()),
+        ));
+
+        let text = std::iter::once(format!("{}\n", title))
+            .chain(rows.iter().map(|(k, v)| format!("{}: {}", k, v)))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let html = format!(
+            "<h3>{}</h3><table>{}</table><p><small>ibc-monitor</small></p>",
+            escape_html(&title),
+            rows.iter()
+                .map(|(k, v)| format!("<tr><th align=\"left\">{}</th><td>{}</td></tr>", k, escape_html(v)))
+                .collect::<String>()
+        );
+
+        tracing::info!(chain = %c.chain_id, client_id = %c.client_id, %label, "email");
+
+        self.send(subject, text, html).await
+    }
+
+    pub async fn send_message(&self, msg: &Message) -> Result<()> {
+        let html = format!(
+            "<h3>{}</h3>{}<p><small>ibc-monitor</small></p>",
+            escape_html(&msg.title),
+            msg.html
+        );
+        self.send(format!("[ibc-monitor] {}", msg.title), msg.text.clone(), html).await
+    }
+
+    async fn send(&self, subject: String, text: String, html: String) -> Result<()> {
+        let mut builder = lettre::Message::builder().from(self.from.clone()).subject(subject);
+        for to in &self.to {
+            builder = builder.to(to.clone());
+        }
+        let msg = builder.multipart(MultiPart::alternative_plain_html(text, html))?;
+
+        self.transport.send(msg).await?;
+        Ok(())
+    }
+}
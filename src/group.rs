@@ -0,0 +1,103 @@
+use crate::config::{GroupBy, GroupConfig};
+use crate::digest::client_line;
+use crate::notify::{escape_html, Alert, Message};
+use crate::outbox::Pending;
+use crate::types::Status;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertGroup {
+    pub by: GroupBy,
+    pub key: String,
+    pub alerts: Vec<Alert>,
+}
+
+// splits a notifier's queue into batches that are delivered as one message each,
+// keeping the order in which each group first appeared
+pub fn batches(queue: Vec<Pending>, cfg: Option<&GroupConfig>) -> Vec<Vec<Pending>> {
+    let Some(cfg) = cfg else { return queue.into_iter().map(|p| vec![p]).collect() };
+
+    let mut groups: Vec<(String, Vec<Pending>)> = Vec::new();
+    for p in queue {
+        let key = group_key(cfg.by, &p.alert);
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, g)) => g.push(p),
+            None => groups.push((key, vec![p])),
+        }
+    }
+
+    let mut batches = Vec::new();
+    for (_, mut group) in groups {
+        while !group.is_empty() {
+            let rest = group.split_off(cfg.max.max(1).min(group.len()));
+            batches.push(std::mem::replace(&mut group, rest));
+        }
+    }
+    batches
+}
+
+fn group_key(by: GroupBy, alert: &Alert) -> String {
+    match (by, &alert.client.status) {
+        (GroupBy::Chain, _) => alert.client.chain_id.clone(),
+        (GroupBy::Reason, Status::Error { reason }) => reason.clone(),
+        (GroupBy::Reason, status) => status.label().to_string(),
+    }
+}
+
+impl AlertGroup {
+    pub fn new(by: GroupBy, batch: &[Pending]) -> Self {
+        Self {
+            by,
+            key: group_key(by, &batch[0].alert),
+            alerts: batch.iter().map(|p| p.alert.clone()).collect(),
+        }
+    }
+
+    pub fn title(&self) -> String {
+        let statuses: Vec<&Status> = self.alerts.iter().map(|a| &a.client.status).collect();
+        let emoji = match statuses.iter().all(|s| s.label() == statuses[0].label()) {
+            true => statuses[0].emoji(),
+            false => "🚨",
+        };
+        let n = self.alerts.len();
+        match (self.by, statuses[0]) {
+            (GroupBy::Chain, _) => format!("{} ibc alert: {} clients on {} changed state", emoji, n, self.key),
+            (GroupBy::Reason, Status::Error { .. }) => format!("{} ibc alert: {} clients failing: {}", emoji, n, self.key),
+            (GroupBy::Reason, status) => format!("{} ibc alert: {} clients {}", emoji, n, status.label()),
+        }
+    }
+
+    fn lines(&self) -> Vec<String> {
+        self.alerts
+            .iter()
+            .map(|a| match &a.previous {
+                Some(prev) => format!("{} (was {})", client_line(&a.client), prev.label()),
+                None => client_line(&a.client),
+            })
+            .collect()
+    }
+
+    pub fn message(&self) -> Message {
+        let lines = self.lines();
+        Message {
+            title: self.title(),
+            text: lines.join("\n"),
+            html: format!(
+                "<ul>{}</ul>",
+                lines.iter().map(|l| format!("<li>{}</li>", escape_html(l))).collect::<String>()
+            ),
+        }
+    }
+
+    pub fn context(&self) -> serde_json::Value {
+        serde_json::json!({
+            "title": self.title(),
+            "group_by": self.by,
+            "key": self.key,
+            "count": self.alerts.len(),
+            "alerts": self.alerts.iter().map(Alert::context).collect::<Vec<_>>(),
+            "text": self.lines().join("\n"),
+            "timestamp": chrono::Utc::now(),
+        })
+    }
+}
@@ -1,19 +1,38 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
-use tracing_subscriber::EnvFilter;
+use std::sync::Arc;
+use tendermint_rpc::Client;
+use tracing_subscriber::{filter::LevelFilter, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
 
 mod config;
+mod digest;
+mod discover;
+mod email;
+mod group;
+mod matrix;
 mod metrics;
 mod monitor;
+mod notify;
+mod outbox;
 mod output;
+mod push;
+mod route;
 mod server;
+mod signing;
+mod silence;
 mod state;
+mod store;
+mod telemetry;
+mod template;
+mod tls;
 mod types;
 mod webhook;
 
-use config::Config;
+use config::{Config, DiscoverConfig, DiscoverFilter, HttpAuth};
 use monitor::Monitor;
+use silence::{Matcher, NewSilence, Silences};
+use telemetry::Telemetry;
 
 #[derive(Parser)]
 #[command(name = "ibc-monitor")]
@@ -33,10 +52,103 @@ enum Commands {
         #[arg(short, long, default_value = "monitor.toml")]
         config: PathBuf,
     },
+    /// run a check in the running daemon and print its result
+    Trigger {
+        #[arg(short, long, default_value = "monitor.toml")]
+        config: PathBuf,
+        /// only check the monitor with this name
+        #[arg(short, long)]
+        monitor: Option<String>,
+        /// daemon address (default: derived from metrics_addr)
+        #[arg(long)]
+        url: Option<String>,
+        /// skip certificate verification, e.g. for a self-signed global.tls cert
+        #[arg(long)]
+        insecure: bool,
+    },
+    Silence {
+        #[arg(short, long, default_value = "monitor.toml")]
+        config: PathBuf,
+        #[command(subcommand)]
+        action: SilenceAction,
+    },
+    /// list the clients, connections and channels on a chain, or print [[monitors]] entries for them
+    Discover {
+        /// grpc endpoint of the chain to inspect
+        #[arg(long)]
+        grpc: String,
+        /// rpc endpoint, used for the generated monitors and to look up the chain id
+        #[arg(long)]
+        rpc: String,
+        /// skip the chain id lookup
+        #[arg(long)]
+        chain_id: Option<String>,
+        /// only clients of these counterparty chains, `*` matches any characters
+        #[arg(long)]
+        counterparty: Vec<String>,
+        /// only these clients
+        #[arg(long)]
+        client: Vec<String>,
+        /// channel states to generate monitors for (default: open)
+        #[arg(long)]
+        state: Vec<String>,
+        /// print [[monitors]] entries instead of the table
+        #[arg(long)]
+        toml: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SilenceAction {
+    Add {
+        #[arg(long)]
+        chain: Option<String>,
+        #[arg(long)]
+        client: Option<String>,
+        #[arg(long)]
+        counterparty: Option<String>,
+        /// monitor name pattern, `*` matches any characters
+        #[arg(long)]
+        name: Option<String>,
+        /// only silence while the client stays in this status (acknowledgement)
+        #[arg(long)]
+        status: Option<String>,
+        /// e.g. 30m, 4h, 2d, 1w
+        #[arg(short, long, default_value = "4h")]
+        duration: String,
+        #[arg(short, long)]
+        reason: String,
+    },
+    List,
+    Remove {
+        id: String,
+    },
+}
+
+impl Commands {
+    fn config(&self) -> Option<&PathBuf> {
+        match self {
+            Commands::Check { config }
+            | Commands::Run { config }
+            | Commands::Trigger { config, .. }
+            | Commands::Silence { config, .. } => Some(config),
+            Commands::Discover { .. } => None,
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let mut cfg = match cli.command.config() {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    if let Ok(token) = std::env::var("API_TOKEN") {
+        cfg.global.api_token = Some(token);
+    }
+    let telemetry = Telemetry::new(cfg.global.otel.as_ref())?;
+
     let json_output = std::env::var("LOG_FORMAT")
         .map(|f| f.eq_ignore_ascii_case("json"))
         .unwrap_or(false);
@@ -44,37 +156,41 @@ async fn main() -> Result<()> {
     let filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new("info"));
 
-    if json_output {
-        tracing_subscriber::fmt()
-            .json()
-            .with_env_filter(filter)
-            .init();
+    let fmt = if json_output {
+        tracing_subscriber::fmt::layer().json().boxed()
     } else {
-        tracing_subscriber::fmt()
-            .with_env_filter(filter)
-            .init();
-    }
+        tracing_subscriber::fmt::layer().boxed()
+    };
+    tracing_subscriber::registry()
+        .with(fmt.with_filter(filter))
+        .with(telemetry.layer().with_filter(LevelFilter::INFO))
+        .init();
 
     // Initialize metrics
-    metrics::init();
-    let prometheus_handle = metrics_exporter_prometheus::PrometheusBuilder::new()
-        .install_recorder()?;
+    let prometheus_handle = metrics::install(telemetry.recorder())?;
 
-    let cli = Cli::parse();
     match cli.command {
-        Commands::Check { config } => {
-            let cfg = Config::load(&config)?;
+        Commands::Check { .. } => {
             let webhook_url = std::env::var("WEBHOOK_URL").ok()
                 .or_else(|| cfg.global.webhook_url.clone());
-            let monitor = Monitor::new(cfg, webhook_url);
+            if let Ok(secret) = std::env::var("WEBHOOK_SECRET") {
+                cfg.global.webhook_auth.secret = Some(secret);
+            }
+            let push = cfg.global.push.clone();
+            let monitor = Monitor::new(cfg, webhook_url)?;
             let results = monitor.check_all().await;
             output::print_results(&results);
+            if let Some(push) = push {
+                push::push(&push, prometheus_handle.render()).await?;
+            }
         }
-        Commands::Run { config } => {
-            let cfg = Config::load(&config)?;
+        Commands::Run { .. } => {
             let webhook_url = std::env::var("WEBHOOK_URL").ok()
                 .or_else(|| cfg.global.webhook_url.clone());
-            let monitor = Monitor::new(cfg.clone(), webhook_url);
+            if let Ok(secret) = std::env::var("WEBHOOK_SECRET") {
+                cfg.global.webhook_auth.secret = Some(secret);
+            }
+            let monitor = Arc::new(Monitor::new(cfg.clone(), webhook_url)?);
             
             // Start metrics server if enabled
             if cfg.global.metrics_enabled.unwrap_or(true) {
@@ -82,12 +198,117 @@ async fn main() -> Result<()> {
                     .as_deref()
                     .unwrap_or("0.0.0.0:9090")
                     .parse()?;
-                tokio::spawn(server::run(addr, prometheus_handle));
+                let server = server::bind(addr, cfg.global.clone(), prometheus_handle, monitor.clone()).await?;
+                tokio::spawn(async move {
+                    if let Err(e) = server.run().await {
+                        tracing::error!("http server failed: {:#}", e);
+                    }
+                });
             }
             
             monitor.run().await?;
         }
+        Commands::Trigger { monitor, url, insecure, .. } => {
+            let url = url.unwrap_or_else(|| {
+                let addr = cfg.global.metrics_addr.as_deref().unwrap_or("0.0.0.0:9090");
+                let scheme = if cfg.global.tls.is_some() { "https" } else { "http" };
+                format!("{}://{}", scheme, addr.replace("0.0.0.0", "127.0.0.1"))
+            });
+            let client = reqwest::Client::builder().danger_accept_invalid_certs(insecure).build()?;
+            let mut req = client.post(format!("{}/api/v1/check", url.trim_end_matches('/')));
+            if let Some(monitor) = &monitor {
+                req = req.query(&[("monitor", monitor)]);
+            }
+            match cfg.global.admin_auth() {
+                Some(HttpAuth::Bearer { token }) => req = req.bearer_auth(token),
+                Some(HttpAuth::Basic { username, password }) => req = req.basic_auth(username, Some(password)),
+                None => {}
+            }
+            let res = req.send().await?;
+            if !res.status().is_success() {
+                anyhow::bail!("check failed: {} {}", res.status(), res.text().await?);
+            }
+            output::print_results(&res.json().await?);
+        }
+        Commands::Silence { action, .. } => {
+            let dir = cfg.global.state_dir
+                .ok_or_else(|| anyhow::anyhow!("silences require global.state_dir to be set"))?;
+            let silences = Silences::new(Some(dir.join("silences.json")));
+            match action {
+                SilenceAction::Add { chain, client, counterparty, name, status, duration, reason } => {
+                    let s = silences.add(NewSilence {
+                        matcher: Matcher {
+                            chain_id: chain,
+                            client_id: client,
+                            counterparty,
+                            name,
+                            status,
+                        },
+                        reason,
+                        duration,
+                    }).await?;
+                    println!("added silence {} until {}", s.id, s.expires.format("%Y-%m-%d %H:%M:%S UTC"));
+                }
+                SilenceAction::List => {
+                    for s in silences.list().await? {
+                        let m = &s.matcher;
+                        let matcher = [
+                            ("chain", &m.chain_id),
+                            ("client", &m.client_id),
+                            ("counterparty", &m.counterparty),
+                            ("name", &m.name),
+                            ("status", &m.status),
+                        ]
+                        .iter()
+                        .filter_map(|(k, v)| v.as_ref().map(|v| format!("{}={}", k, v)))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                        println!("{:<14} until {}  {:<50} {}", s.id, s.expires.format("%Y-%m-%d %H:%M UTC"), matcher, s.reason);
+                    }
+                }
+                SilenceAction::Remove { id } => {
+                    if !silences.remove(&id).await? {
+                        anyhow::bail!("no silence {}", id);
+                    }
+                    println!("removed silence {}", id);
+                }
+            }
+        }
+        Commands::Discover { grpc, rpc, chain_id, counterparty, client, state, toml } => {
+            let chain_id = match chain_id {
+                Some(id) => id,
+                None => tendermint_rpc::HttpClient::new(rpc.as_str())?
+                    .status()
+                    .await
+                    .context("failed to look up chain id, pass --chain-id")?
+                    .node_info
+                    .network
+                    .to_string(),
+            };
+            let discover = DiscoverConfig {
+                chain_id,
+                rpc_addr: rpc,
+                grpc_addr: grpc,
+                include: DiscoverFilter { counterparty, channel_state: state },
+                exclude: DiscoverFilter::default(),
+                refresh_interval: 0,
+            };
+            discover::validate(&discover)?;
+
+            let clients: Vec<_> = discover::discover(&discover.grpc_addr)
+                .await?
+                .into_iter()
+                .filter(|c| client.is_empty() || client.contains(&c.client_id))
+                .filter(|c| discover::counterparty_included(&discover, &c.client_state.chain_id))
+                .collect();
+            let updates = discover::last_updates(&discover.grpc_addr, &clients).await?;
+            match toml {
+                true => output::print_monitors(&discover::monitors(&discover, &clients), &clients, &updates)?,
+                false => output::print_discovered(&discover.chain_id, &clients, &updates),
+            }
+        }
     }
 
+    telemetry.shutdown();
     Ok(())
 }
@@ -0,0 +1,130 @@
+use crate::config::MatrixConfig;
+use crate::group::AlertGroup;
+use crate::notify::{escape_html, Alert, Message};
+use crate::types::Status;
+use anyhow::{anyhow, Result};
+use reqwest::Url;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+static TXN_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone)]
+pub struct MatrixClient {
+    client: reqwest::Client,
+    homeserver: String,
+    room_id: String,
+    access_token: String,
+}
+
+#[derive(Serialize)]
+struct RoomMessage<'a> {
+    msgtype: &'a str,
+    body: String,
+    format: &'a str,
+    formatted_body: String,
+}
+
+impl MatrixClient {
+    pub fn new(cfg: &MatrixConfig) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap(),
+            homeserver: cfg.homeserver.clone(),
+            room_id: cfg.room_id.clone(),
+            access_token: cfg.access_token.clone(),
+        }
+    }
+
+    pub async fn send_alert(&self, alert: &Alert) -> Result<()> {
+        let (chain, client_id, status) = (&alert.client.chain_id, &alert.client.client_id, &alert.client.status);
+        let label = status.label();
+        let (title, fields) = (alert.title(), alert.fields());
+
+        let msg = RoomMessage {
+            msgtype: "m.text",
+            body: std::iter::once(title.clone())
+                .chain(fields.iter().map(|(k, v)| format!("{}: {}", k, v)))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            format: "org.matrix.custom.html",
+            formatted_body: format!(
+                "<p><strong><font color=\"{}\">{}</font></strong></p><ul>{}</ul>",
+                html_color(status),
+                escape_html(&title),
+                fields
+                    .iter()
+                    .map(|(k, v)| format!("<li>{}: <code>{}</code></li>", k, escape_html(v)))
+                    .collect::<String>()
+            ),
+        };
+
+        tracing::info!(%chain, %client_id, %label, "matrix");
+
+        self.send(&msg, &txn_id(alert)).await
+    }
+
+    pub async fn send_group(&self, group: &AlertGroup) -> Result<()> {
+        self.send(&room_message(&group.message()), &txn_id(&group.alerts)).await
+    }
+
+    pub async fn send_message(&self, msg: &Message) -> Result<()> {
+        let txn_id = format!(
+            "ibc-monitor-{}-{}",
+            chrono::Utc::now().timestamp_millis(),
+            TXN_COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        self.send(&room_message(msg), &txn_id).await
+    }
+
+    async fn send(&self, msg: &RoomMessage<'_>, txn_id: &str) -> Result<()> {
+        let res = self
+            .client
+            .put(self.send_url(txn_id)?)
+            .bearer_auth(&self.access_token)
+            .json(msg)
+            .send()
+            .await?;
+        if !res.status().is_success() {
+            return Err(anyhow!("matrix {}: {}", res.status(), res.text().await?));
+        }
+        Ok(())
+    }
+
+    fn send_url(&self, txn_id: &str) -> Result<Url> {
+        let mut url = Url::parse(&self.homeserver)?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow!("invalid homeserver url: {}", self.homeserver))?
+            .pop_if_empty()
+            .extend(["_matrix", "client", "v3", "rooms", &self.room_id, "send", "m.room.message", txn_id]);
+        Ok(url)
+    }
+}
+
+fn room_message(msg: &Message) -> RoomMessage<'static> {
+    RoomMessage {
+        msgtype: "m.text",
+        body: format!("{}\n\n{}", msg.title, msg.text),
+        format: "org.matrix.custom.html",
+        formatted_body: format!("<p><strong>{}</strong></p>{}", escape_html(&msg.title), msg.html),
+    }
+}
+
+// alerts are stored in the outbox unchanged, so retries and restarts reuse the transaction id
+// and the homeserver drops a resend of a message it already accepted
+fn txn_id(alerts: &impl Serialize) -> String {
+    let digest = Sha256::digest(serde_json::to_vec(alerts).unwrap_or_default());
+    format!("ibc-monitor-{}", hex::encode(&digest[..16]))
+}
+
+fn html_color(status: &Status) -> &'static str {
+    match status {
+        Status::Healthy { .. } => "#2eb886",
+        Status::Warning { .. } => "#daa038",
+        _ => "#a30200",
+    }
+}
@@ -1,43 +1,195 @@
-use crate::types::Status;
-use metrics::{counter, describe_counter, describe_gauge, gauge};
+use crate::telemetry::OtelRecorder;
+use crate::types::{ClientStatus, Status, Suppression};
+use metrics::{counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use metrics_util::layers::FanoutBuilder;
 
-pub fn init() {
-    describe_gauge!("ibc_client_hours_until_expiry", "Hours until IBC client expires");
-    describe_counter!("ibc_client_checks_total", "Total number of client checks");
-    describe_gauge!("ibc_client_status", "Current status of IBC client (1=active, 0=inactive)");
-    describe_gauge!("ibc_monitor_check_duration_seconds", "Duration of monitor check in seconds");
+const STATUSES: [&str; 5] = ["healthy", "warning", "critical", "expired", "error"];
+const DURATION_BUCKETS: [f64; 12] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+pub fn install(otel: Option<OtelRecorder>) -> anyhow::Result<PrometheusHandle> {
+    let prometheus = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Suffix("_duration_seconds".to_string()), &DURATION_BUCKETS)?
+        .build_recorder();
+    let handle = prometheus.handle();
+    let installed = match otel {
+        Some(otel) => metrics::set_global_recorder(
+            FanoutBuilder::default().add_recorder(prometheus).add_recorder(otel).build(),
+        )
+        .is_ok(),
+        None => metrics::set_global_recorder(prometheus).is_ok(),
+    };
+    anyhow::ensure!(installed, "metrics recorder already installed");
+    init();
+    Ok(handle)
+}
+
+fn init() {
+    describe_gauge!("ibc_client_hours_until_expiry", "Hours until IBC client expires (negative once expired)");
+    describe_gauge!("ibc_client_status", "Current status of IBC client (1 for the active status label, 0 otherwise)");
+    describe_gauge!("ibc_client_last_update_timestamp_seconds", "Unix time of the client's latest consensus state");
+    describe_gauge!("ibc_client_expiry_timestamp_seconds", "Unix time at which the client's trusting period ends");
+    describe_gauge!("ibc_client_trusting_period_seconds", "Trusting period of the client");
+    describe_gauge!("ibc_client_unbonding_period_seconds", "Unbonding period of the client");
+    describe_counter!("ibc_client_checks_total", "Total number of client checks by resulting status");
+    describe_counter!("ibc_client_check_errors_total", "Failed client checks by error reason");
+    describe_histogram!("ibc_monitor_check_duration_seconds", "Duration of a single monitor check");
+    describe_counter!("ibc_monitor_checks_total", "Total number of monitor checks by result");
+    describe_histogram!("ibc_monitor_sweep_duration_seconds", "Duration of a full sweep over all monitors");
+    describe_histogram!("ibc_monitor_rpc_duration_seconds", "Latency of gRPC calls by endpoint, method and status code");
+    describe_counter!("ibc_monitor_rpc_errors_total", "Failed gRPC calls by endpoint, method and status code");
+    describe_counter!("ibc_monitor_notifications_total", "Notification delivery attempts by notifier and result");
+    describe_gauge!("ibc_monitor_outbox_pending", "Alerts waiting in the outbox for delivery");
+    describe_gauge!("ibc_client_flapping", "Whether notifications for the client are suppressed due to flapping (1=flapping)");
+    describe_gauge!("ibc_client_pending_confirmations", "Consecutive checks observed for an unconfirmed state change");
+    describe_gauge!("ibc_client_silenced", "Whether notifications for the client are muted by a silence (1=silenced)");
+    describe_counter!("ibc_monitor_notifications_suppressed_total", "Notifications suppressed by hysteresis or flap detection");
+}
+
+pub fn record_client_check(client: &ClientStatus) {
+    // clients whose id could not be resolved are told apart by their channel
+    let (chain, id) = (client.chain_id.clone(), client.id().to_string());
+    let label = client.status.label();
+
+    // status carries no counterparty so a failed check, which has none, updates the same series
+    for status in STATUSES {
+        let labels = [("chain", chain.clone()), ("client", id.clone()), ("status", status.to_string())];
+        gauge!("ibc_client_status", &labels).set(if status == label { 1.0 } else { 0.0 });
+    }
+    counter!("ibc_client_checks_total", &[("status", label.to_string())]).increment(1);
+
+    let hours = match &client.status {
+        Status::Healthy { hours_until_expiry: h }
+        | Status::Warning { hours_until_expiry: h }
+        | Status::Critical { hours_until_expiry: h } => *h,
+        Status::Expired { hours_since_expiry: h } => -h.abs(),
+        Status::Error { reason } => {
+            counter!(
+                "ibc_client_check_errors_total",
+                &[("chain", chain), ("client", id), ("reason", error_reason(reason).to_string())]
+            )
+            .increment(1);
+            return;
+        }
+    };
+
+    // a client whose state is gone has expired without telling us its counterparty or periods
+    let counterparty = client.counterparty_chain_id.clone().unwrap_or_default();
+    let labels = [("chain", chain), ("client", id), ("counterparty", counterparty)];
+    gauge!("ibc_client_hours_until_expiry", &labels).set(hours);
+    if client.counterparty_chain_id.is_none() {
+        return;
+    }
+    gauge!("ibc_client_trusting_period_seconds", &labels).set(client.trusting_period.num_seconds() as f64);
+    gauge!("ibc_client_unbonding_period_seconds", &labels).set(client.unbonding_period.num_seconds() as f64);
+    if let Some(last_update) = client.last_update {
+        gauge!("ibc_client_last_update_timestamp_seconds", &labels).set(last_update.timestamp() as f64);
+        gauge!("ibc_client_expiry_timestamp_seconds", &labels)
+            .set((last_update + client.trusting_period).timestamp() as f64);
+    }
 }
 
-pub fn record_client_check(chain: &str, client: &str, counterparty: &str, status: &Status, hours: f64) {
+// the exporter cannot drop series, so a client that is no longer monitored reports no active
+// status and NaN instead of its last values, which alert rules comparing against them ignore
+pub fn forget_client(chain: &str, client: &str, counterparty: Option<&str>) {
+    for status in STATUSES {
+        let labels = [("chain", chain.to_string()), ("client", client.to_string()), ("status", status.to_string())];
+        gauge!("ibc_client_status", &labels).set(0.0);
+    }
+    record_suppression(chain, client, None);
+
     let labels = [
         ("chain", chain.to_string()),
         ("client", client.to_string()),
-        ("counterparty", counterparty.to_string()),
+        ("counterparty", counterparty.unwrap_or_default().to_string()),
     ];
+    if counterparty.is_none() {
+        gauge!("ibc_client_hours_until_expiry", &labels).set(f64::NAN);
+        return;
+    }
+    for name in [
+        "ibc_client_hours_until_expiry",
+        "ibc_client_trusting_period_seconds",
+        "ibc_client_unbonding_period_seconds",
+        "ibc_client_last_update_timestamp_seconds",
+        "ibc_client_expiry_timestamp_seconds",
+    ] {
+        gauge!(name, &labels).set(f64::NAN);
+    }
+}
 
-    match status {
-        Status::Healthy { .. } | Status::Warning { .. } | Status::Critical { .. } => {
-            gauge!("ibc_client_hours_until_expiry", &labels).set(hours);
-            gauge!("ibc_client_status", &labels).set(1.0);
-        }
-        Status::Expired { .. } => {
-            gauge!("ibc_client_hours_until_expiry", &labels).set(-hours);
-            gauge!("ibc_client_status", &labels).set(0.0);
-        }
-        Status::Error { .. } => gauge!("ibc_client_status", &labels).set(0.0),
+// error reasons are free text, bucket them to keep label cardinality bounded
+fn error_reason(reason: &str) -> &'static str {
+    let reason = reason.to_lowercase();
+    if reason.contains("timed out") || reason.contains("timeout") || reason.contains("deadline") {
+        "timeout"
+    } else if reason.contains("transport error") || reason.contains("connection") || reason.contains("dns") {
+        "connection"
+    } else if reason.contains("not found") {
+        "not_found"
+    } else if reason.contains("wrong type") || reason.contains("decode") {
+        "decode"
+    } else if reason.contains("query failed") || reason.contains("status:") {
+        "query"
+    } else {
+        "other"
     }
+}
 
-    let label = match status {
-        Status::Healthy { .. } => "healthy",
-        Status::Warning { .. } => "warning",
-        Status::Critical { .. } => "critical",
-        Status::Expired { .. } => "expired",
-        Status::Error { .. } => "error",
-    };
+pub fn record_monitor_check(monitor: &str, seconds: f64, ok: bool) {
+    histogram!("ibc_monitor_check_duration_seconds", &[("monitor", monitor.to_string())]).record(seconds);
+    counter!(
+        "ibc_monitor_checks_total",
+        &[("monitor", monitor.to_string()), ("result", if ok { "ok" } else { "error" }.to_string())]
+    )
+    .increment(1);
+}
 
-    counter!("ibc_client_checks_total", &[("status", label.to_string())]).increment(1);
+pub fn record_sweep_duration(seconds: f64) {
+    histogram!("ibc_monitor_sweep_duration_seconds").record(seconds);
 }
 
-pub fn record_check_duration(duration: f64) {
-    gauge!("ibc_monitor_check_duration_seconds").set(duration);
-}
\ No newline at end of file
+pub fn record_rpc(endpoint: &str, method: &'static str, code: &str, seconds: f64) {
+    let labels = [
+        ("endpoint", endpoint.to_string()),
+        ("method", method.to_string()),
+        ("code", code.to_string()),
+    ];
+    histogram!("ibc_monitor_rpc_duration_seconds", &labels).record(seconds);
+    if code != "ok" {
+        counter!("ibc_monitor_rpc_errors_total", &labels).increment(1);
+    }
+}
+
+pub fn record_notification(notifier: &str, result: &'static str) {
+    counter!(
+        "ibc_monitor_notifications_total",
+        &[("notifier", notifier.to_string()), ("result", result.to_string())]
+    )
+    .increment(1);
+}
+
+pub fn record_outbox_pending(pending: usize) {
+    gauge!("ibc_monitor_outbox_pending").set(pending as f64);
+}
+
+pub fn record_suppression(chain: &str, client: &str, suppressed: Option<&Suppression>) {
+    let labels = [("chain", chain.to_string()), ("client", client.to_string())];
+    let (flapping, pending, silenced) = match suppressed {
+        Some(Suppression::Flapping { .. }) => (1.0, 0.0, 0.0),
+        Some(Suppression::Pending { confirmations, .. }) => (0.0, *confirmations as f64, 0.0),
+        Some(Suppression::Silenced { .. }) => (0.0, 0.0, 1.0),
+        None => (0.0, 0.0, 0.0),
+    };
+    gauge!("ibc_client_flapping", &labels).set(flapping);
+    gauge!("ibc_client_pending_confirmations", &labels).set(pending);
+    gauge!("ibc_client_silenced", &labels).set(silenced);
+}
+
+pub fn record_notification_suppressed(suppressed: &Suppression) {
+    counter!(
+        "ibc_monitor_notifications_suppressed_total",
+        &[("reason", suppressed.label().to_string())]
+    )
+    .increment(1);
+}
@@ -1,11 +1,18 @@
 use crate::config::{Config, MonitorConfig};
-use crate::types::{CheckResult, ClientStatus, MonitorResult, Status, Summary};
-use crate::{metrics, state::StateTracker, webhook::WebhookClient};
+use crate::types::{CheckResult, ClientStatus, MonitorResult, Status, Summary, Suppression};
+use crate::digest::DigestScheduler;
+use crate::discover::Discovery;
+use crate::notify::{Alert, AlertKind, Notifiers};
+use crate::outbox::Outbox;
+use crate::route::Routes;
+use crate::silence::Silences;
+use crate::store::{ResultStore, StatusEvent};
+use crate::{metrics, state::StateTracker};
 use anyhow::{anyhow, Context, Result};
 use chrono::{DateTime, Duration, Utc};
 use ibc_proto::ibc::core::{
     channel::v1::{query_client::QueryClient as ChannelQueryClient, QueryChannelRequest},
-    client::v1::{query_client::QueryClient, QueryClientStateRequest, QueryConsensusStateRequest},
+    client::v1::{query_client::QueryClient, Height, QueryClientStateRequest, QueryConsensusStateRequest},
     connection::v1::{query_client::QueryClient as ConnectionQueryClient, QueryConnectionRequest},
 };
 use ibc_proto::{
@@ -13,126 +20,182 @@ use ibc_proto::{
     ibc::lightclients::tendermint::v1::{ClientState as TendermintClientState, ConsensusState as TendermintConsensusState},
 };
 use prost::Message;
+use std::collections::{HashMap, HashSet};
 use tokio::time;
 use tonic::transport::Channel;
-use tracing::info;
+use tracing::{info, Instrument};
 
 pub struct Monitor {
     config: Config,
-    webhook: WebhookClient,
+    discovery: Discovery,
+    notifiers: Notifiers,
+    routes: Routes,
+    silences: Silences,
     state: StateTracker,
+    store: ResultStore,
+    // client ids looked up from channels, which never change client once open
+    resolved: tokio::sync::Mutex<HashMap<(String, String), String>>,
+    // discovered clients checked by the last sweep with their last known counterparty
+    checked: tokio::sync::Mutex<HashMap<(String, String), Option<String>>>,
 }
 
 impl Monitor {
-    pub fn new(config: Config, webhook_url: Option<String>) -> Self {
-        Self { 
+    pub fn new(config: Config, webhook_url: Option<String>) -> Result<Self> {
+        let notifiers = Notifiers::new(
+            webhook_url,
+            config.global.webhook_recovery,
+            &config.global.webhook_auth,
+            &config.notifiers,
+            config.global.retry.clone(),
+            config.global.group.clone(),
+            Outbox::load(config.global.state_dir.as_ref().map(|d| d.join("outbox.json")))?,
+        )?;
+        Ok(Self { 
+            routes: Routes::new(config.routes.clone(), &notifiers.names())?,
+            notifiers,
+            silences: Silences::new(config.global.state_dir.as_ref().map(|d| d.join("silences.json"))),
+            state: StateTracker::load(
+                config.global.state_dir.as_ref().map(|d| d.join("state.json")),
+                config.global.confirm.clone(),
+                config.global.flap.clone(),
+            )?,
+            store: ResultStore::default(),
+            discovery: Discovery::new(config.discover.clone())?,
+            resolved: Default::default(),
+            checked: Default::default(),
             config,
-            webhook: WebhookClient::new(webhook_url),
-            state: StateTracker::new(),
-        }
+        })
+    }
+
+    pub fn silences(&self) -> Silences {
+        self.silences.clone()
+    }
+
+    pub fn store(&self) -> ResultStore {
+        self.store.clone()
     }
 
     pub async fn check_all(&self) -> CheckResult {
+        let discovered = self.discovery.monitors().await;
+        let result = self.sweep(self.monitors(&discovered).await, true).await;
+        self.forget_vanished(&discovered, &result).await;
+        result
+    }
+
+    // clients dropped from discovery would otherwise keep their last metrics and state forever
+    async fn forget_vanished(&self, discovered: &[MonitorConfig], result: &CheckResult) {
+        let names: HashSet<_> = discovered.iter().map(|m| m.name.as_str()).collect();
+        let statuses = || result.monitors.iter().flat_map(|m| &m.clients);
+        let mut checked = self.checked.lock().await;
+        let previous = std::mem::take(&mut *checked);
+        for m in result.monitors.iter().filter(|m| names.contains(m.name.as_str())) {
+            for s in &m.clients {
+                let key = (s.chain_id.clone(), s.client_id.clone());
+                let counterparty = s.counterparty_chain_id.clone().or_else(|| previous.get(&key).cloned().flatten());
+                checked.insert(key, counterparty);
+            }
+        }
+
+        let mut forgotten = 0;
+        for ((chain, client), counterparty) in previous {
+            // a configured monitor may have taken the client over
+            if statuses().any(|s| s.chain_id == chain && s.client_id == client) {
+                continue;
+            }
+            info!(%chain, %client, "client no longer discovered, forgetting it");
+            metrics::forget_client(&chain, &client, counterparty.as_deref());
+            self.state.forget(&format!("{}:{}", chain, client)).await;
+            forgotten += 1;
+        }
+        if forgotten > 0 {
+            self.state.persist().await;
+        }
+    }
+
+    // configured monitors take precedence over discovered ones for the same client
+    async fn monitors<'a>(&'a self, discovered: &'a [MonitorConfig]) -> Vec<&'a MonitorConfig> {
+        let mut covered = HashSet::new();
+        if !discovered.is_empty() {
+            for m in &self.config.monitors {
+                // a monitor that cannot be resolved reports its own error, the discovered one still runs
+                if let Ok(id) = self.client_id(m).await {
+                    covered.insert((m.chain_id.clone(), id));
+                }
+            }
+        }
+        self.config.monitors.iter().chain(uncovered(covered, discovered)).collect()
+    }
+
+    // out-of-band check from the api, scoped to one monitor when a name is given. it only
+    // refreshes metrics and results: confirmations, flap history and notifications are left
+    // to the scheduled sweeps so triggering cannot skew them
+    pub async fn trigger(&self, name: Option<&str>) -> Option<CheckResult> {
+        let discovered = self.discovery.monitors().await;
+        let selected: Vec<_> = self
+            .monitors(&discovered)
+            .await
+            .into_iter()
+            .filter(|m| name.is_none_or(|n| m.name == n))
+            .collect();
+        if selected.is_empty() {
+            return None;
+        }
+        info!(monitors = selected.len(), "running triggered check");
+        let result = self.sweep(selected, false).await;
+        match name {
+            Some(_) => self.store.merge(&result).await,
+            None => self.store.update(&result).await,
+        }
+        Some(result)
+    }
+
+    #[tracing::instrument(name = "sweep", skip_all, fields(monitors = selected.len(), observe = observe))]
+    async fn sweep(&self, selected: Vec<&MonitorConfig>, observe: bool) -> CheckResult {
         let start = std::time::Instant::now();
         let mut all_statuses = Vec::new();
         let mut monitors = Vec::new();
 
-        for cfg in &self.config.monitors {
-            let status = match self.check_client(cfg).await {
+        for cfg in selected {
+            let check_start = std::time::Instant::now();
+            let span = tracing::info_span!(
+                "check",
+                monitor = %cfg.name,
+                chain = %cfg.chain_id,
+                client = cfg.client_id.as_deref(),
+                endpoint = %endpoint_host(&cfg.grpc_addr),
+                otel.status_code = tracing::field::Empty,
+            );
+            let result = self.check_client(cfg).instrument(span.clone()).await;
+            if result.is_err() {
+                span.record("otel.status_code", "ERROR");
+            }
+            metrics::record_monitor_check(&cfg.name, check_start.elapsed().as_secs_f64(), result.is_ok());
+            let mut status = match result {
                 Ok(mut s) => {
                     s.channel = cfg.channel.clone();
                     s
                 }
-                Err(e) => self.error_status(cfg, e.to_string()),
+                Err(e) => self.error_status(cfg, e.to_string()).await,
             };
-            
-            // Record metrics and check for alerts
-            if let Some(counterparty) = &status.counterparty_chain_id {
-                let hours = match &status.status {
-                    Status::Healthy { hours_until_expiry } |
-                    Status::Warning { hours_until_expiry } |
-                    Status::Critical { hours_until_expiry } => *hours_until_expiry,
-                    Status::Expired { hours_since_expiry } => -*hours_since_expiry,
-                    Status::Error { .. } => 0.0,
-                };
-                
-                metrics::record_client_check(
-                    &status.chain_id,
-                    &status.client_id,
-                    counterparty,
-                    &status.status,
-                    hours,
-                );
-                
-                let key = format!("{}:{}", status.chain_id, status.client_id);
-                if self.state.has_changed(&key, &status.status).await {
-                    match &status.status {
-                        Status::Healthy { .. } => {
-                            tracing::info!(
-                                chain = %status.chain_id,
-                                client = %status.client_id,
-                                counterparty = %counterparty,
-                                hours_left = %hours,
-                                "client recovered"
-                            );
-                        }
-                        Status::Warning { .. } => {
-                            tracing::warn!(
-                                chain = %status.chain_id,
-                                client = %status.client_id,
-                                counterparty = %counterparty,
-                                hours_left = %hours,
-                                "client expiry warning"
-                            );
-                        }
-                        Status::Critical { .. } => {
-                            tracing::error!(
-                                chain = %status.chain_id,
-                                client = %status.client_id,
-                                counterparty = %counterparty,
-                                hours_left = %hours,
-                                "client expiry critical"
-                            );
-                        }
-                        Status::Expired { .. } => {
-                            tracing::error!(
-                                chain = %status.chain_id,
-                                client = %status.client_id,
-                                counterparty = %counterparty,
-                                hours_ago = %hours.abs(),
-                                "client expired"
-                            );
-                        }
-                        Status::Error { reason } => {
-                            tracing::error!(
-                                chain = %status.chain_id,
-                                client = %status.client_id,
-                                counterparty = %counterparty,
-                                reason = %reason,
-                                "client error"
-                            );
-                        }
-                    }
-                    
-                    // Optionally still send webhook for critical states
-                    if !matches!(&status.status, Status::Healthy { .. }) {
-                        if let Err(e) = self.webhook
-                            .send_alert(&status.chain_id, &status.client_id, Some(counterparty), &status.status)
-                            .await 
-                        {
-                            tracing::debug!("webhook failed: {}", e);
-                        }
-                    }
-                }
+
+            match observe {
+                true => self.track(cfg, &mut status).await,
+                false => metrics::record_client_check(&status),
             }
-            
+            self.store.progress().await;
+
             all_statuses.push(status.clone());
             monitors.push(MonitorResult {
+                name: cfg.name.clone(),
                 clients: vec![status],
             });
         }
 
-        metrics::record_check_duration(start.elapsed().as_secs_f64());
+        if observe {
+            self.state.persist().await;
+            self.notifiers.flush().await;
+            metrics::record_sweep_duration(start.elapsed().as_secs_f64());
+        }
 
         CheckResult {
             timestamp: Utc::now(),
@@ -141,65 +204,250 @@ impl Monitor {
         }
     }
 
+    async fn track(&self, cfg: &MonitorConfig, status: &mut ClientStatus) {
+        let hours = match &status.status {
+            Status::Healthy { hours_until_expiry } |
+            Status::Warning { hours_until_expiry } |
+            Status::Critical { hours_until_expiry } => *hours_until_expiry,
+            Status::Expired { hours_since_expiry } => -*hours_since_expiry,
+            Status::Error { .. } => 0.0,
+        };
+
+        metrics::record_client_check(status);
+        // clients that could not be read are only reported through metrics and the api unless opted in
+        if status.counterparty_chain_id.is_none() && !self.config.global.alert_on_error {
+            return;
+        }
+        let counterparty = status.counterparty_chain_id.clone().unwrap_or_else(|| "?".to_string());
+
+        let key = format!("{}:{}", status.chain_id, status.id());
+        let observation = self.state.observe(&key, &status.status).await;
+        status.suppressed = observation.suppressed;
+        if status.suppressed.is_none() {
+            if let Some(s) = self.silences.matching(cfg, status).await {
+                status.suppressed = Some(Suppression::Silenced { id: s.id, reason: s.reason, expires: s.expires });
+            }
+        }
+        metrics::record_suppression(&status.chain_id, status.id(), status.suppressed.as_ref());
+
+        if let Some(suppressed @ Suppression::Pending { .. }) = &status.suppressed {
+            tracing::debug!(
+                chain = %status.chain_id,
+                client = %status.client_id,
+                %suppressed,
+                "awaiting confirmation"
+            );
+            return;
+        }
+
+        // milestones crossed together with a state change are covered by the transition alert
+        let milestone = match &status.status {
+            Status::Healthy { hours_until_expiry: h }
+            | Status::Warning { hours_until_expiry: h }
+            | Status::Critical { hours_until_expiry: h } => {
+                self.state.milestone(&key, *h, &self.config.global.milestones).await
+            }
+            _ => None,
+        };
+
+        if let Some(transition) = &observation.transition {
+            self.store.publish(StatusEvent::Transition {
+                timestamp: Utc::now(),
+                monitor: cfg.name.clone(),
+                client: Box::new(status.clone()),
+                previous: transition.previous.clone(),
+            });
+        }
+
+        let alert = if let Some(transition) = observation.transition {
+            match &status.status {
+                Status::Healthy { .. } => {
+                    tracing::info!(
+                        chain = %status.chain_id,
+                        client = %status.client_id,
+                        counterparty = %counterparty,
+                        hours_left = %hours,
+                        "client recovered"
+                    );
+                }
+                Status::Warning { .. } => {
+                    tracing::warn!(
+                        chain = %status.chain_id,
+                        client = %status.client_id,
+                        counterparty = %counterparty,
+                        hours_left = %hours,
+                        "client expiry warning"
+                    );
+                }
+                Status::Critical { .. } => {
+                    tracing::error!(
+                        chain = %status.chain_id,
+                        client = %status.client_id,
+                        counterparty = %counterparty,
+                        hours_left = %hours,
+                        "client expiry critical"
+                    );
+                }
+                Status::Expired { .. } => {
+                    tracing::error!(
+                        chain = %status.chain_id,
+                        client = %status.client_id,
+                        counterparty = %counterparty,
+                        hours_ago = %hours.abs(),
+                        "client expired"
+                    );
+                }
+                Status::Error { reason } => {
+                    tracing::error!(
+                        chain = %status.chain_id,
+                        client = %status.client_id,
+                        counterparty = %counterparty,
+                        reason = %reason,
+                        "client error"
+                    );
+                }
+            }
+
+            match (&status.status, transition.degraded_since) {
+                (Status::Healthy { .. }, Some(degraded_since)) => {
+                    Some((AlertKind::Recovery { degraded_since }, transition.previous))
+                }
+                (Status::Healthy { .. }, None) => None,
+                _ => Some((AlertKind::Transition, transition.previous)),
+            }
+        } else if let Some(hours) = milestone {
+            tracing::warn!(
+                chain = %status.chain_id,
+                client = %status.client_id,
+                counterparty = %counterparty,
+                status = %status.status.label(),
+                milestone = hours,
+                "client expiry milestone"
+            );
+            Some((AlertKind::Milestone { hours }, Some(status.status.clone())))
+        } else if let Some(every) = self.config.global.repeat.interval(&status.status) {
+            self.state.reminder(&key, every).await.map(|reminder| {
+                tracing::warn!(
+                    chain = %status.chain_id,
+                    client = %status.client_id,
+                    counterparty = %counterparty,
+                    status = %status.status.label(),
+                    count = reminder.count,
+                    "client still unresolved"
+                );
+                (
+                    AlertKind::Reminder { count: reminder.count, since: reminder.since },
+                    Some(status.status.clone()),
+                )
+            })
+        } else {
+            None
+        };
+
+        let Some((kind, previous)) = alert else { return };
+        if let Some(suppressed) = &status.suppressed {
+            tracing::info!(
+                chain = %status.chain_id,
+                client = %status.client_id,
+                %suppressed,
+                "notification suppressed"
+            );
+            metrics::record_notification_suppressed(suppressed);
+            return;
+        }
+
+        let alert = Alert {
+            monitor: cfg.clone(),
+            client: status.clone(),
+            previous,
+            kind,
+        };
+        self.notifiers.enqueue(&alert, self.routes.select(&alert).as_ref()).await;
+    }
+
     pub async fn run(&self) -> Result<()> {
         info!("monitoring interval: {}s", self.config.global.check_interval);
+        let mut digest = self.config.global.digest.clone().map(DigestScheduler::new);
         loop {
-            crate::output::print_results(&self.check_all().await);
+            self.store.progress().await;
+            let result = self.check_all().await;
+            crate::output::print_results(&result);
+            self.store.update(&result).await;
+            if let Some(d) = digest.as_mut().and_then(|s| s.observe(&result)) {
+                info!(clients = d.clients.len(), errored = d.errored.len(), "sending digest");
+                self.notifiers.send_digest(&d).await;
+            }
             time::sleep(time::Duration::from_secs(self.config.global.check_interval)).await;
         }
     }
 
     async fn discover_client_id(&self, grpc_addr: &str, channel_id: &str) -> Result<String> {
-        let channel = Channel::from_shared(grpc_addr.to_string())?
-            .connect_timeout(std::time::Duration::from_secs(10))
-            .connect()
-            .await?;
+        let endpoint = endpoint_host(grpc_addr);
+        let channel = connect(grpc_addr).await?;
 
-        let chan = ChannelQueryClient::new(channel.clone())
-            .channel(QueryChannelRequest {
+        let chan = rpc(
+            &endpoint,
+            "channel",
+            ChannelQueryClient::new(channel.clone()).channel(QueryChannelRequest {
                 port_id: "transfer".to_string(),
                 channel_id: channel_id.to_string(),
-            })
-            .await?
-            .into_inner()
-            .channel
-            .ok_or_else(|| anyhow!("channel not found"))?;
+            }),
+        )
+        .await?
+        .channel
+        .ok_or_else(|| anyhow!("channel not found"))?;
 
         let conn_id = chan.connection_hops
             .first()
             .ok_or_else(|| anyhow!("no connection hops"))?
             .clone();
 
-        let client_id = ConnectionQueryClient::new(channel)
-            .connection(QueryConnectionRequest { connection_id: conn_id })
-            .await?
-            .into_inner()
-            .connection
-            .ok_or_else(|| anyhow!("connection not found"))?
-            .client_id;
+        let client_id = rpc(
+            &endpoint,
+            "connection",
+            ConnectionQueryClient::new(channel).connection(QueryConnectionRequest { connection_id: conn_id }),
+        )
+        .await?
+        .connection
+        .ok_or_else(|| anyhow!("connection not found"))?
+        .client_id;
 
         info!("discovered {} for {}", client_id, channel_id);
         Ok(client_id)
     }
 
+    async fn client_id(&self, cfg: &MonitorConfig) -> Result<String> {
+        if let Some(id) = &cfg.client_id {
+            return Ok(id.clone());
+        }
+        let key = (cfg.chain_id.clone(), cfg.channel.clone());
+        if let Some(id) = self.resolved.lock().await.get(&key) {
+            return Ok(id.clone());
+        }
+        let id = self.discover_client_id(&cfg.grpc_addr, &cfg.channel).await?;
+        self.resolved.lock().await.insert(key, id.clone());
+        Ok(id)
+    }
+
     async fn check_client(&self, cfg: &MonitorConfig) -> Result<ClientStatus> {
-        let client_id = match &cfg.client_id {
-            Some(id) => id.clone(),
-            None => self.discover_client_id(&cfg.grpc_addr, &cfg.channel).await?,
-        };
+        let client_id = self.client_id(cfg).await?;
+        if cfg.client_id.is_none() {
+            tracing::Span::current().record("client", client_id.as_str());
+        }
 
-        let channel = Channel::from_shared(cfg.grpc_addr.clone())?
-            .connect_timeout(std::time::Duration::from_secs(10))
-            .connect()
-            .await?;
+        let endpoint = endpoint_host(&cfg.grpc_addr);
+        let channel = connect(&cfg.grpc_addr).await?;
 
         let mut client = QueryClient::new(channel);
 
-        let resp = match client
-            .client_state(QueryClientStateRequest { client_id: client_id.clone() })
-            .await
+        let resp = match rpc(
+            &endpoint,
+            "client_state",
+            client.client_state(QueryClientStateRequest { client_id: client_id.clone() }),
+        )
+        .await
         {
-            Ok(r) => r.into_inner(),
+            Ok(r) => r,
             Err(status) if status.code() == tonic::Code::NotFound || 
                           status.message().contains("not found") ||
                           status.message().contains("expired") => {
@@ -213,6 +461,7 @@ impl Monitor {
                     latest_height: None,
                     counterparty_chain_id: None,
                     channel: String::new(),
+                    suppressed: None,
                 });
             }
             Err(e) => return Err(anyhow!("client query failed: {}", e)),
@@ -236,25 +485,8 @@ impl Monitor {
         
         let counterparty_chain_id = client_state.chain_id.clone();
 
-        let consensus_state_any = client
-            .consensus_state(QueryConsensusStateRequest {
-                client_id: client_id.clone(),
-                revision_number: latest_height.revision_number,
-                revision_height: latest_height.revision_height,
-                latest_height: false,
-            })
-            .await?
-            .into_inner()
-            .consensus_state
-            .ok_or_else(|| anyhow!("no consensus state"))?;
-
-        let consensus_state = parse_tendermint_consensus_state(&consensus_state_any)?;
-        let ts = consensus_state.timestamp
-            .ok_or_else(|| anyhow!("no timestamp"))?;
-        
-        let last_update = DateTime::from_timestamp(ts.seconds, ts.nanos as u32)
-            .ok_or_else(|| anyhow!("invalid timestamp"))?;
-        
+        let last_update = consensus_timestamp(&mut client, &endpoint, &client_id, latest_height).await?;
+
         let expires_at = last_update + trusting_period;
         let time_until_expiry = expires_at - Utc::now();
 
@@ -276,13 +508,17 @@ impl Monitor {
             latest_height: Some((latest_height.revision_number, latest_height.revision_height)),
             counterparty_chain_id: Some(counterparty_chain_id),
             channel: String::new(),
+            suppressed: None,
         })
     }
 
-    fn error_status(&self, cfg: &MonitorConfig, error: String) -> ClientStatus {
+    // a failed check of a resolved channel keeps the client id, so its state and metrics
+    // stay on the same key as the healthy checks before and after it
+    async fn error_status(&self, cfg: &MonitorConfig, error: String) -> ClientStatus {
+        let resolved = self.resolved.lock().await.get(&(cfg.chain_id.clone(), cfg.channel.clone())).cloned();
         ClientStatus {
             chain_id: cfg.chain_id.clone(),
-            client_id: cfg.client_id.clone().unwrap_or_else(|| "unknown".to_string()),
+            client_id: cfg.client_id.clone().or(resolved).unwrap_or_else(|| "unknown".to_string()),
             status: Status::Error { reason: error },
             last_update: None,
             trusting_period: Duration::zero(),
@@ -290,11 +526,92 @@ impl Monitor {
             latest_height: None,
             counterparty_chain_id: None,
             channel: cfg.channel.clone(),
+            suppressed: None,
         }
     }
 }
 
-fn parse_tendermint_client_state(any: &Any) -> Result<TendermintClientState> {
+// discovered monitors for clients not in `covered`, once per client even when [[discover]] entries overlap
+fn uncovered(mut covered: HashSet<(String, String)>, discovered: &[MonitorConfig]) -> Vec<&MonitorConfig> {
+    discovered
+        .iter()
+        .filter(|d| covered.insert((d.chain_id.clone(), d.client_id.clone().unwrap_or_default())))
+        .collect()
+}
+
+pub fn endpoint_host(addr: &str) -> String {
+    reqwest::Url::parse(addr)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_string))
+        .unwrap_or_else(|| addr.to_string())
+}
+
+pub async fn connect(grpc_addr: &str) -> Result<Channel> {
+    let endpoint = endpoint_host(grpc_addr);
+    let span = tracing::info_span!("query", %endpoint, method = "connect", code = tracing::field::Empty);
+    let start = std::time::Instant::now();
+    let res = Channel::from_shared(grpc_addr.to_string())?
+        .connect_timeout(std::time::Duration::from_secs(10))
+        .connect()
+        .instrument(span.clone())
+        .await;
+    let code = if res.is_ok() { "ok" } else { "transport_error" };
+    span.record("code", code);
+    metrics::record_rpc(&endpoint, "connect", code, start.elapsed().as_secs_f64());
+    Ok(res?)
+}
+
+pub async fn rpc<T>(
+    endpoint: &str,
+    method: &'static str,
+    call: impl std::future::Future<Output = Result<tonic::Response<T>, tonic::Status>>,
+) -> Result<T, tonic::Status> {
+    let span = tracing::info_span!("query", endpoint, method, code = tracing::field::Empty);
+    let start = std::time::Instant::now();
+    let res = call.instrument(span.clone()).await;
+    let code = match &res {
+        Ok(_) => "ok".to_string(),
+        // NotFound -> not_found
+        Err(status) => format!("{:?}", status.code()).chars().fold(String::new(), |mut s, c| {
+            if c.is_uppercase() && !s.is_empty() {
+                s.push('_');
+            }
+            s.push(c.to_ascii_lowercase());
+            s
+        }),
+    };
+    span.record("code", code.as_str());
+    metrics::record_rpc(endpoint, method, &code, start.elapsed().as_secs_f64());
+    res.map(tonic::Response::into_inner)
+}
+
+// time of the consensus state the client was last updated to
+pub async fn consensus_timestamp(
+    client: &mut QueryClient<Channel>,
+    endpoint: &str,
+    client_id: &str,
+    height: Height,
+) -> Result<DateTime<Utc>> {
+    let consensus_state_any = rpc(
+        endpoint,
+        "consensus_state",
+        client.consensus_state(QueryConsensusStateRequest {
+            client_id: client_id.to_string(),
+            revision_number: height.revision_number,
+            revision_height: height.revision_height,
+            latest_height: false,
+        }),
+    )
+    .await?
+    .consensus_state
+    .ok_or_else(|| anyhow!("no consensus state"))?;
+
+    let consensus_state = parse_tendermint_consensus_state(&consensus_state_any)?;
+    let ts = consensus_state.timestamp.ok_or_else(|| anyhow!("no timestamp"))?;
+    DateTime::from_timestamp(ts.seconds, ts.nanos as u32).ok_or_else(|| anyhow!("invalid timestamp"))
+}
+
+pub fn parse_tendermint_client_state(any: &Any) -> Result<TendermintClientState> {
     (any.type_url == "/ibc.lightclients.tendermint.v1.ClientState")
         .then(|| TendermintClientState::decode(&any.value[..]))
         .ok_or_else(|| anyhow!("wrong type: {}", any.type_url))?
@@ -306,4 +623,80 @@ fn parse_tendermint_consensus_state(any: &Any) -> Result<TendermintConsensusStat
         .then(|| TendermintConsensusState::decode(&any.value[..]))
         .ok_or_else(|| anyhow!("wrong type: {}", any.type_url))?
         .context("decode failed")
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(chain: &str, client: Option<&str>, channel: &str) -> MonitorConfig {
+        MonitorConfig {
+            name: format!("{} {:?} {}", chain, client, channel),
+            description: String::new(),
+            chain_id: chain.to_string(),
+            rpc_addr: String::new(),
+            grpc_addr: String::new(),
+            client_id: client.map(str::to_string),
+            channel: channel.to_string(),
+        }
+    }
+
+    #[test]
+    fn discovered_monitors_are_deduplicated_by_client() {
+        // a configured monitor on channel-3 resolved to the client discovery checks through channel-0
+        let covered = HashSet::from([("penumbra-1".to_string(), "07-tendermint-0".to_string())]);
+        let discovered = [
+            monitor("penumbra-1", Some("07-tendermint-0"), "channel-0"),
+            monitor("penumbra-1", Some("07-tendermint-1"), "channel-1"),
+            // the same client again from an overlapping [[discover]] entry
+            monitor("penumbra-1", Some("07-tendermint-1"), "channel-1"),
+            monitor("noble-1", Some("07-tendermint-0"), "channel-0"),
+        ];
+        let kept: Vec<_> = uncovered(covered, &discovered)
+            .into_iter()
+            .map(|m| (m.chain_id.as_str(), m.client_id.as_deref().unwrap()))
+            .collect();
+        assert_eq!(kept, [("penumbra-1", "07-tendermint-1"), ("noble-1", "07-tendermint-0")]);
+    }
+
+    fn healthy(cfg: &MonitorConfig, client_id: &str) -> ClientStatus {
+        ClientStatus {
+            chain_id: cfg.chain_id.clone(),
+            client_id: client_id.to_string(),
+            status: Status::Healthy { hours_until_expiry: 200.0 },
+            last_update: Some(Utc::now()),
+            trusting_period: Duration::days(14),
+            unbonding_period: Duration::days(21),
+            latest_height: Some((1, 100)),
+            counterparty_chain_id: Some("osmosis-1".to_string()),
+            channel: cfg.channel.clone(),
+            suppressed: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn errors_of_a_channel_monitor_share_the_client_key() {
+        let cfg = monitor("penumbra-1", None, "channel-0");
+        let mut config = Config { monitors: vec![cfg.clone()], ..Default::default() };
+        config.global.alert_on_error = true;
+        config.global.confirm.error = Some(2);
+        let monitor = Monitor::new(config, None).unwrap();
+        monitor
+            .resolved
+            .lock()
+            .await
+            .insert(("penumbra-1".to_string(), "channel-0".to_string()), "07-tendermint-0".to_string());
+
+        // each error follows a healthy check, so neither may count as the second confirmation
+        for _ in 0..2 {
+            monitor.track(&cfg, &mut healthy(&cfg, "07-tendermint-0")).await;
+            let mut error = monitor.error_status(&cfg, "deadline has elapsed".to_string()).await;
+            assert_eq!(error.id(), "07-tendermint-0");
+            monitor.track(&cfg, &mut error).await;
+            assert!(matches!(error.suppressed, Some(Suppression::Pending { confirmations: 1, .. })));
+        }
+        let mut recovered = healthy(&cfg, "07-tendermint-0");
+        monitor.track(&cfg, &mut recovered).await;
+        assert!(recovered.suppressed.is_none());
+    }
+}
@@ -0,0 +1,376 @@
+use crate::config::{GroupBy, GroupConfig, MonitorConfig, NotifierConfig, NotifierKind, RetryConfig, WebhookAuthConfig};
+use crate::digest::Digest;
+use crate::email::EmailClient;
+use crate::group::{self, AlertGroup};
+use crate::matrix::MatrixClient;
+use crate::metrics;
+use crate::outbox::{Outbox, Pending};
+use crate::template::TemplateClient;
+use crate::types::{format_duration, ClientStatus, Status};
+use crate::webhook::WebhookClient;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::{task::JoinSet, time};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    pub monitor: MonitorConfig,
+    pub client: ClientStatus,
+    pub previous: Option<Status>,
+    #[serde(default)]
+    pub kind: AlertKind,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertKind {
+    #[default]
+    Transition,
+    Recovery { degraded_since: DateTime<Utc> },
+    Reminder { count: u32, since: DateTime<Utc> },
+    Milestone { hours: u64 },
+}
+
+impl Alert {
+    pub fn title(&self) -> String {
+        let status = &self.client.status;
+        match &self.kind {
+            AlertKind::Transition => format!("{} ibc alert: {}", status.emoji(), status.label()),
+            AlertKind::Recovery { .. } => format!(
+                "{} ibc recovered: {} -> {}",
+                status.emoji(),
+                self.previous.as_ref().map_or("degraded", |p| p.label()),
+                status.label()
+            ),
+            AlertKind::Reminder { count, .. } => {
+                let urgency = match status {
+                    Status::Critical { hours_until_expiry: h } if *h < 6.0 => "expiring imminently",
+                    Status::Critical { .. } => "still critical",
+                    Status::Warning { .. } => "still in warning",
+                    Status::Expired { .. } => "still expired",
+                    Status::Error { .. } => "still failing",
+                    Status::Healthy { .. } => "healthy",
+                };
+                format!("{} ibc reminder #{}: {} - {}", status.emoji(), count, urgency, status.describe())
+            }
+            AlertKind::Milestone { hours } => format!(
+                "{} ibc countdown: {} - less than {}h left ({})",
+                status.emoji(),
+                status.label(),
+                hours,
+                status.describe()
+            ),
+        }
+    }
+
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let c = &self.client;
+        let mut fields = vec![
+            ("chain", c.chain_id.clone()),
+            ("client", c.client_id.clone()),
+            ("counterparty", c.counterparty_chain_id.clone().unwrap_or_else(|| "?".into())),
+            ("status", format!("{} - {}", c.status.label(), c.status.describe())),
+        ];
+        if let AlertKind::Recovery { degraded_since } = &self.kind {
+            fields.push(("degraded for", format_duration(Utc::now() - *degraded_since)));
+            fields.push(("expires at", self.expires_at().unwrap_or_else(|| "unknown".into())));
+        }
+        if let AlertKind::Reminder { since, .. } = &self.kind {
+            fields.push(("unresolved for", format_duration(Utc::now() - *since)));
+        }
+        fields
+    }
+
+    pub fn expires_at(&self) -> Option<String> {
+        let c = &self.client;
+        c.last_update
+            .map(|t| (t + c.trusting_period).format("%Y-%m-%d %H:%M:%S UTC").to_string())
+    }
+
+    pub fn context(&self) -> serde_json::Value {
+        let c = &self.client;
+        serde_json::json!({
+            "monitor": self.monitor,
+            "client": c,
+            "previous": self.previous,
+            "kind": self.kind,
+            "title": self.title(),
+            "chain_id": c.chain_id,
+            "client_id": c.client_id,
+            "counterparty": c.counterparty_chain_id.as_deref().unwrap_or("?"),
+            "channel": c.channel,
+            "status": c.status.label(),
+            "description": c.status.describe(),
+            "emoji": c.status.emoji(),
+            "expires_at": c.last_update.map(|t| t + c.trusting_period),
+            "degraded_for": match &self.kind {
+                AlertKind::Recovery { degraded_since } => Some(format_duration(Utc::now() - *degraded_since)),
+                _ => None,
+            },
+            "unresolved_for": match &self.kind {
+                AlertKind::Reminder { since, .. } => Some(format_duration(Utc::now() - *since)),
+                _ => None,
+            },
+            "timestamp": Utc::now(),
+        })
+    }
+}
+
+pub struct Message {
+    pub title: String,
+    pub text: String,
+    pub html: String,
+}
+
+#[derive(Clone)]
+pub enum Notifier {
+    Slack(WebhookClient),
+    Matrix(MatrixClient),
+    Email(EmailClient),
+    Template(TemplateClient),
+}
+
+impl Notifier {
+    pub fn from_config(cfg: &NotifierConfig) -> Result<Self> {
+        Ok(match &cfg.kind {
+            NotifierKind::Slack(c) => Notifier::Slack(WebhookClient::new(Some(c.url.clone()), &c.auth)),
+            NotifierKind::Matrix(c) => Notifier::Matrix(MatrixClient::new(c)),
+            NotifierKind::Email(c) => Notifier::Email(
+                EmailClient::new(c).with_context(|| format!("notifier {}", cfg.name))?,
+            ),
+            NotifierKind::Template(c) => Notifier::Template(
+                TemplateClient::new(c).with_context(|| format!("notifier {}", cfg.name))?,
+            ),
+        })
+    }
+
+    pub async fn send(&self, alert: &Alert) -> Result<()> {
+        match self {
+            Notifier::Slack(n) => n.send_alert(alert).await,
+            Notifier::Matrix(n) => n.send_alert(alert).await,
+            Notifier::Email(n) => n.send_alert(alert).await,
+            Notifier::Template(n) => n.send_alert(alert).await,
+        }
+    }
+
+    pub fn digests(&self) -> bool {
+        match self {
+            Notifier::Template(n) => n.digests(),
+            _ => true,
+        }
+    }
+
+    pub fn groups(&self) -> bool {
+        match self {
+            Notifier::Template(n) => n.groups(),
+            _ => true,
+        }
+    }
+
+    pub async fn send_group(&self, group: &AlertGroup) -> Result<()> {
+        tracing::info!(key = %group.key, alerts = group.alerts.len(), "grouped alert");
+        match self {
+            Notifier::Slack(n) => n.send_message(&group.message()).await,
+            Notifier::Matrix(n) => n.send_group(group).await,
+            Notifier::Email(n) => n.send_message(&group.message()).await,
+            Notifier::Template(n) => n.send_group(group).await,
+        }
+    }
+
+    pub async fn send_digest(&self, digest: &Digest) -> Result<()> {
+        match self {
+            Notifier::Slack(n) => n.send_message(&digest.message()).await,
+            Notifier::Matrix(n) => n.send_message(&digest.message()).await,
+            Notifier::Email(n) => n.send_message(&digest.message()).await,
+            Notifier::Template(n) => n.send_digest(digest).await,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Entry {
+    name: String,
+    notifier: Notifier,
+    recovery: bool,
+    digest: bool,
+}
+
+#[derive(Clone)]
+pub struct Notifiers {
+    notifiers: Arc<Vec<Entry>>,
+    outbox: Outbox,
+    retry: RetryConfig,
+    group: Option<GroupConfig>,
+}
+
+impl Notifiers {
+    pub fn new(
+        webhook_url: Option<String>,
+        webhook_recovery: bool,
+        webhook_auth: &WebhookAuthConfig,
+        configs: &[NotifierConfig],
+        retry: RetryConfig,
+        group: Option<GroupConfig>,
+        outbox: Outbox,
+    ) -> Result<Self> {
+        let mut notifiers = Vec::new();
+        if let Some(url) = webhook_url.filter(|u| !u.is_empty()) {
+            notifiers.push(Entry {
+                name: "webhook".to_string(),
+                notifier: Notifier::Slack(WebhookClient::new(Some(url), webhook_auth)),
+                recovery: webhook_recovery,
+                digest: true,
+            });
+        }
+        for cfg in configs {
+            let notifier = Notifier::from_config(cfg)?;
+            notifiers.push(Entry {
+                name: cfg.name.clone(),
+                digest: cfg.digest && notifier.digests(),
+                notifier,
+                recovery: cfg.recovery,
+            });
+        }
+        Ok(Self {
+            notifiers: Arc::new(notifiers),
+            outbox,
+            retry,
+            group,
+        })
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.notifiers.iter().map(|e| e.name.clone()).collect()
+    }
+
+    pub async fn enqueue(&self, alert: &Alert, targets: Option<&BTreeSet<String>>) {
+        let recovery = matches!(alert.kind, AlertKind::Recovery { .. });
+        for entry in self
+            .notifiers
+            .iter()
+            .filter(|e| e.recovery || !recovery)
+            .filter(|e| targets.is_none_or(|t| t.contains(&e.name)))
+        {
+            self.outbox
+                .push(Pending {
+                    notifier: entry.name.clone(),
+                    alert: alert.clone(),
+                    created: Utc::now(),
+                    attempts: 0,
+                })
+                .await;
+        }
+    }
+
+    pub async fn send_digest(&self, digest: &Digest) {
+        for entry in self.notifiers.iter().filter(|e| e.digest) {
+            match entry.notifier.send_digest(digest).await {
+                Ok(()) => metrics::record_notification(&entry.name, "sent"),
+                Err(e) => {
+                    metrics::record_notification(&entry.name, "failed");
+                    tracing::warn!(notifier = %entry.name, "digest failed: {:#}", e);
+                }
+            }
+        }
+    }
+
+    pub async fn flush(&self) {
+        let pending = self.outbox.take().await;
+        if pending.is_empty() {
+            return;
+        }
+
+        let max_age = chrono::Duration::hours(self.retry.max_age as i64);
+        let mut queues: BTreeMap<String, Vec<Pending>> = BTreeMap::new();
+        for p in pending {
+            if Utc::now() - p.created > max_age || !self.notifiers.iter().any(|e| e.name == p.notifier) {
+                tracing::warn!(
+                    notifier = %p.notifier,
+                    monitor = %p.alert.monitor.name,
+                    attempts = p.attempts,
+                    "dropping undelivered alert"
+                );
+                metrics::record_notification(&p.notifier, "dropped");
+                continue;
+            }
+            queues.entry(p.notifier.clone()).or_default().push(p);
+        }
+
+        let mut tasks = JoinSet::new();
+        for (name, queue) in queues {
+            let Some(entry) = self.notifiers.iter().find(|e| e.name == name) else { continue };
+            let (notifier, retry) = (entry.notifier.clone(), self.retry.clone());
+            let batches = group::batches(queue, self.group.as_ref().filter(|_| entry.notifier.groups()));
+            let by = self.group.as_ref().map(|g| g.by).unwrap_or_default();
+            tasks.spawn(async move { deliver(&name, &notifier, batches, by, &retry).await });
+        }
+
+        let mut failed = Vec::new();
+        while let Some(res) = tasks.join_next().await {
+            match res {
+                Ok(mut f) => failed.append(&mut f),
+                Err(e) => tracing::error!("notification task failed: {}", e),
+            }
+        }
+
+        let remaining = self.outbox.requeue(failed).await;
+        metrics::record_outbox_pending(remaining);
+    }
+}
+
+async fn deliver(
+    name: &str,
+    notifier: &Notifier,
+    batches: Vec<Vec<Pending>>,
+    by: GroupBy,
+    retry: &RetryConfig,
+) -> Vec<Pending> {
+    let max_backoff = Duration::from_secs(retry.max_backoff);
+    let mut batches = batches.into_iter();
+
+    while let Some(mut batch) = batches.next() {
+        let mut backoff = Duration::from_secs(retry.initial_backoff);
+        for attempt in 1..=retry.attempts.max(1) {
+            batch.iter_mut().for_each(|p| p.attempts += 1);
+            let res = match batch.as_slice() {
+                [p] => notifier.send(&p.alert).await,
+                _ => notifier.send_group(&AlertGroup::new(by, &batch)).await,
+            };
+            match res {
+                Ok(()) => {
+                    metrics::record_notification(name, "sent");
+                    break;
+                }
+                Err(e) => {
+                    metrics::record_notification(name, "failed");
+                    tracing::warn!(
+                        notifier = %name,
+                        monitor = %batch[0].alert.monitor.name,
+                        alerts = batch.len(),
+                        attempts = batch[0].attempts,
+                        "notification failed: {:#}",
+                        e
+                    );
+                    if attempt == retry.attempts.max(1) {
+                        // leave this and everything queued behind it for the next flush
+                        return batch.into_iter().chain(batches.flatten()).collect();
+                    }
+                    time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(max_backoff);
+                }
+            }
+        }
+    }
+    Vec::new()
+}
+
+pub fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
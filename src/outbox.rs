@@ -0,0 +1,65 @@
+use crate::notify::Alert;
+use crate::state::{load_json, save_json};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, sync::Arc};
+use tokio::sync::Mutex;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pending {
+    pub notifier: String,
+    pub alert: Alert,
+    pub created: DateTime<Utc>,
+    pub attempts: u32,
+}
+
+#[derive(Clone, Default)]
+pub struct Outbox {
+    pending: Arc<Mutex<Vec<Pending>>>,
+    path: Option<PathBuf>,
+}
+
+impl Outbox {
+    pub fn load(path: Option<PathBuf>) -> Result<Self> {
+        let pending: Vec<Pending> = match &path {
+            Some(p) => load_json(p)?,
+            None => Vec::new(),
+        };
+        if !pending.is_empty() {
+            tracing::info!(count = pending.len(), "loaded undelivered alerts from outbox");
+        }
+        Ok(Self {
+            pending: Arc::new(Mutex::new(pending)),
+            path,
+        })
+    }
+
+    // saved right away so an alert is never lost to a crash while state already records it as sent.
+    // taking leaves the file alone until the flush requeues what failed, so a crash mid-flush resends
+    pub async fn push(&self, entry: Pending) {
+        let mut pending = self.pending.lock().await;
+        pending.push(entry);
+        self.save(&pending);
+    }
+
+    pub async fn take(&self) -> Vec<Pending> {
+        std::mem::take(&mut *self.pending.lock().await)
+    }
+
+    pub async fn requeue(&self, mut failed: Vec<Pending>) -> usize {
+        let mut pending = self.pending.lock().await;
+        failed.append(&mut pending);
+        *pending = failed;
+        self.save(&pending);
+        pending.len()
+    }
+
+    fn save(&self, pending: &[Pending]) {
+        if let Some(path) = &self.path {
+            if let Err(e) = save_json(path, &pending) {
+                tracing::warn!("failed to persist outbox: {:#}", e);
+            }
+        }
+    }
+}
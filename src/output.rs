@@ -1,5 +1,9 @@
+use crate::config::MonitorConfig;
+use crate::discover::DiscoveredClient;
 use crate::types::{CheckResult, Status, ClientStatus};
-use chrono::Utc;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
 use colored::*;
 use std::collections::HashMap;
 
@@ -84,8 +88,11 @@ pub fn print_results(result: &CheckResult) {
                 })
                 .unwrap_or_else(|| "unknown".to_string());
             
-            let line = format!("{:<8} {:<30} {:<15} {:<22} {:<15} {:<10} {:<25} {:<20}", 
+            let mut line = format!("{:<8} {:<30} {:<15} {:<22} {:<15} {:<10} {:<25} {:<20}", 
                 status_str, connection, &client.channel, &client.client_id, time_left, periods, height_info, last_update);
+            if let Some(suppressed) = &client.suppressed {
+                line.push_str(&format!(" ({})", suppressed));
+            }
             
             match &client.status {
                 Status::Expired { .. } => println!("{}", line.red()),
@@ -100,4 +107,64 @@ pub fn print_results(result: &CheckResult) {
     println!("summary: {} total | {} healthy | {} warning | {} critical | {} expired | {} errors",
         s.total, s.healthy, s.warning, s.critical, s.expired, s.error
     );
-}
\ No newline at end of file
+}
+fn expiry(client: &DiscoveredClient, last_update: &Result<DateTime<Utc>>) -> String {
+    let Ok(last_update) = last_update else { return "unknown".to_string() };
+    let trusting_period = client.client_state.trusting_period.as_ref().map_or(0, |p| p.seconds);
+    let hours = (*last_update + Duration::seconds(trusting_period) - Utc::now()).num_hours() as f64;
+    match hours {
+        h if h < 0.0 => format!("{:.1}d expired", -h / 24.0),
+        h if h > 24.0 => format!("{:.1}d left", h / 24.0),
+        h => format!("{:.0}h left", h),
+    }
+}
+
+pub fn print_discovered(chain_id: &str, clients: &[DiscoveredClient], updates: &[Result<DateTime<Utc>>]) {
+    println!("ibc clients on {} - {}", chain_id, Utc::now().format("%Y-%m-%d %H:%M:%S UTC"));
+    println!();
+    println!("{:<22} {:<22} {:<15} {:<10} {:<20} channels",
+        "client", "counterparty", "expiry", "trust/ub", "connections");
+    println!("{}", "─".repeat(160));
+
+    for (client, update) in clients.iter().zip(updates) {
+        let cs = &client.client_state;
+        let days = |p: &Option<ibc_proto::google::protobuf::Duration>| p.as_ref().map_or(0, |p| p.seconds / 86400);
+        let periods = format!("{}d/{}d", days(&cs.trusting_period), days(&cs.unbonding_period));
+        let channels = client
+            .channels
+            .iter()
+            .map(|ch| format!("{}/{} ({}, {})", ch.port_id, ch.channel_id, ch.connection_id, ch.state))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let expiry = expiry(client, update);
+        let line = format!("{:<22} {:<22} {:<15} {:<10} {:<20} {}",
+            client.client_id, cs.chain_id, expiry, periods, client.connections.join(","), channels);
+        match expiry.ends_with("expired") {
+            true => println!("{}", line.red()),
+            false => println!("{}", line),
+        }
+    }
+
+    println!("{}", "─".repeat(160));
+    println!("{} tendermint clients, {} with open channels",
+        clients.len(),
+        clients.iter().filter(|c| c.channels.iter().any(|ch| ch.state == "open")).count()
+    );
+}
+
+#[derive(Serialize)]
+struct Monitors<'a> {
+    monitors: &'a [MonitorConfig],
+}
+
+// one commented [[monitors]] block per client, ready to paste into the config
+pub fn print_monitors(monitors: &[MonitorConfig], clients: &[DiscoveredClient], updates: &[Result<DateTime<Utc>>]) -> Result<()> {
+    for monitor in monitors {
+        let found = clients.iter().zip(updates).find(|(c, _)| Some(&c.client_id) == monitor.client_id.as_ref());
+        if let Some((client, update)) = found {
+            println!("# {} ({})", client.client_state.chain_id, expiry(client, update));
+        }
+        println!("{}", toml::to_string(&Monitors { monitors: std::slice::from_ref(monitor) })?);
+    }
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use crate::config::PushConfig;
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use reqwest::Url;
+use std::path::Path;
+use std::time::Duration;
+
+// `check` exits before anything can scrape it, so its final metrics are handed off instead
+pub async fn push(cfg: &PushConfig, metrics: String) -> Result<()> {
+    if let Some(path) = &cfg.textfile {
+        write_textfile(path, &metrics).with_context(|| format!("writing {}", path.display()))?;
+        tracing::info!(path = %path.display(), "wrote metrics textfile");
+    }
+    if let Some(gateway) = &cfg.gateway {
+        let url = grouping_url(gateway, cfg)?;
+        let res = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()?
+            .put(url.clone())
+            .header(reqwest::header::CONTENT_TYPE, "text/plain; version=0.0.4")
+            .body(metrics)
+            .send()
+            .await?;
+        if !res.status().is_success() {
+            return Err(anyhow!("pushgateway {}: {}", res.status(), res.text().await?));
+        }
+        tracing::info!(%url, "pushed metrics");
+    }
+    Ok(())
+}
+
+fn grouping_url(gateway: &str, cfg: &PushConfig) -> Result<Url> {
+    let mut url = Url::parse(gateway).context("invalid pushgateway url")?;
+    {
+        let mut segments = url
+            .path_segments_mut()
+            .map_err(|_| anyhow!("invalid pushgateway url: {}", gateway))?;
+        segments.pop_if_empty().push("metrics");
+        for (label, value) in std::iter::once(("job", &cfg.job)).chain(cfg.instance.iter().map(|i| ("instance", i))) {
+            // the gateway cannot take a `/` in a path segment, even escaped
+            match value.contains('/') {
+                true => segments.extend([format!("{}@base64", label), URL_SAFE_NO_PAD.encode(value)]),
+                false => segments.extend([label.to_string(), value.clone()]),
+            };
+        }
+    }
+    Ok(url)
+}
+
+// node_exporter may read the file at any time, so it is replaced with a rename
+fn write_textfile(path: &Path, metrics: &str) -> Result<()> {
+    let tmp = path.with_extension("prom.tmp");
+    std::fs::write(&tmp, metrics)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use crate::config::RouteConfig;
+use crate::notify::Alert;
+use crate::silence::glob_match;
+use anyhow::{bail, Result};
+use std::collections::BTreeSet;
+
+const SEVERITIES: [&str; 5] = ["healthy", "warning", "critical", "expired", "error"];
+
+#[derive(Clone, Default)]
+pub struct Routes(Vec<RouteConfig>);
+
+impl Routes {
+    pub fn new(routes: Vec<RouteConfig>, notifiers: &[String]) -> Result<Self> {
+        for (i, route) in routes.iter().enumerate() {
+            if let Some(s) = route.severity.iter().find(|s| !SEVERITIES.contains(&s.as_str())) {
+                bail!("route {}: unknown severity {}", i, s);
+            }
+            if let Some(n) = route.notifiers.iter().find(|n| !notifiers.contains(n)) {
+                bail!("route {}: unknown notifier {}", i, n);
+            }
+        }
+        Ok(Self(routes))
+    }
+
+    // notifiers selected by every matching route, falling back to the matching default routes.
+    // None sends to all notifiers, which is what happens without routes or a default route
+    pub fn select(&self, alert: &Alert) -> Option<BTreeSet<String>> {
+        let c = &alert.client;
+        let counterparty = c.counterparty_chain_id.as_deref().unwrap_or("?");
+        let matches = |p: &Option<String>, v: &str| p.as_deref().is_none_or(|p| glob_match(p, v));
+        let matching = |default: bool| {
+            self.0
+                .iter()
+                .filter(move |r| r.default == default)
+                .filter(|r| r.severity.is_empty() || r.severity.iter().any(|s| s == c.status.label()))
+                .filter(|r| matches(&r.chain_id, &c.chain_id))
+                .filter(|r| matches(&r.counterparty, counterparty))
+                .filter(|r| matches(&r.name, &alert.monitor.name))
+                .collect::<Vec<_>>()
+        };
+
+        let mut routes = matching(false);
+        if routes.iter().all(|r| r.notifiers.is_empty()) {
+            routes = matching(true);
+        }
+        // a default route without notifiers drops what no other route picked up
+        (!routes.is_empty()).then(|| routes.iter().flat_map(|r| r.notifiers.iter().cloned()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MonitorConfig;
+    use crate::types::{ClientStatus, Status};
+
+    fn route(severity: &str, notifiers: &[&str], default: bool) -> RouteConfig {
+        RouteConfig {
+            severity: (!severity.is_empty()).then(|| severity.to_string()).into_iter().collect(),
+            chain_id: None,
+            counterparty: None,
+            name: None,
+            notifiers: notifiers.iter().map(|n| n.to_string()).collect(),
+            default,
+        }
+    }
+
+    fn alert(status: Status) -> Alert {
+        Alert {
+            monitor: MonitorConfig {
+                name: "osmosis on penumbra".to_string(),
+                description: String::new(),
+                chain_id: "penumbra-1".to_string(),
+                rpc_addr: String::new(),
+                grpc_addr: String::new(),
+                client_id: None,
+                channel: "channel-0".to_string(),
+            },
+            client: ClientStatus {
+                chain_id: "penumbra-1".to_string(),
+                client_id: "07-tendermint-0".to_string(),
+                status,
+                last_update: None,
+                trusting_period: chrono::Duration::zero(),
+                unbonding_period: chrono::Duration::zero(),
+                latest_height: None,
+                counterparty_chain_id: Some("osmosis-1".to_string()),
+                channel: "channel-0".to_string(),
+                suppressed: None,
+            },
+            previous: None,
+            kind: Default::default(),
+        }
+    }
+
+    fn select(routes: &Routes, status: Status) -> Option<Vec<String>> {
+        routes.select(&alert(status)).map(|s| s.into_iter().collect())
+    }
+
+    #[test]
+    fn unmatched_alerts_go_to_the_default_route() {
+        let names = ["pagerduty".to_string(), "slack".to_string()];
+        let expired = || Status::Expired { hours_since_expiry: 1.0 };
+        let healthy = || Status::Healthy { hours_until_expiry: 200.0 };
+
+        let routes = Routes::new(vec![route("expired", &["pagerduty"], false)], &names).unwrap();
+        assert_eq!(select(&routes, expired()), Some(vec!["pagerduty".to_string()]));
+        assert_eq!(select(&routes, healthy()), None);
+
+        let routes = Routes::new(
+            vec![route("expired", &["pagerduty"], false), route("", &["slack"], true)],
+            &names,
+        )
+        .unwrap();
+        assert_eq!(select(&routes, expired()), Some(vec!["pagerduty".to_string()]));
+        assert_eq!(select(&routes, healthy()), Some(vec!["slack".to_string()]));
+
+        let routes = Routes::new(vec![route("expired", &["pagerduty"], false), route("", &[], true)], &names).unwrap();
+        assert_eq!(select(&routes, healthy()), Some(Vec::new()));
+    }
+}
@@ -1,14 +1,366 @@
-use axum::{routing::get, Router};
+use crate::config::{AuthConfig, GlobalConfig, HttpAuth};
+use crate::monitor::Monitor;
+use crate::silence::{NewSilence, Silence, Silences};
+use crate::store::{ClientDetail, ResultStore, StatusEvent};
+use crate::types::{CheckResult, Summary};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, Request, State,
+    },
+    http::{
+        header::{AUTHORIZATION, WWW_AUTHENTICATE},
+        HeaderMap, Method, StatusCode,
+    },
+    middleware::{self, Next},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Html, IntoResponse, Response,
+    },
+    routing::{delete, get, post},
+    Json, Router,
+};
+use anyhow::Context;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Duration, Utc};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::conn::auto,
+    service::TowerToHyperService,
+};
 use metrics_exporter_prometheus::PrometheusHandle;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::convert::Infallible;
 use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_rustls::TlsAcceptor;
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
 
-pub async fn run(addr: SocketAddr, handle: PrometheusHandle) -> anyhow::Result<()> {
-    axum::serve(
-        tokio::net::TcpListener::bind(addr).await?,
-        Router::new()
-            .route("/metrics", get(move || async move { handle.render() }))
-            .route("/health", get(|| async { "ok" })),
-    )
-    .await?;
-    Ok(())
-}
\ No newline at end of file
+const DASHBOARD: &str = include_str!("dashboard.html");
+
+#[derive(Clone)]
+struct AppState {
+    prometheus: PrometheusHandle,
+    silences: Silences,
+    store: ResultStore,
+    monitor: Arc<Monitor>,
+    auth: AuthConfig,
+    admin_auth: Option<HttpAuth>,
+    started: DateTime<Utc>,
+    live_after: Duration,
+    ready_after: Duration,
+}
+
+pub struct Server {
+    listener: TcpListener,
+    app: Router,
+    tls: Option<TlsAcceptor>,
+}
+
+// binds and loads the certificate up front so a bad address or tls config fails startup
+pub async fn bind(
+    addr: SocketAddr,
+    global: GlobalConfig,
+    handle: PrometheusHandle,
+    monitor: Arc<Monitor>,
+) -> anyhow::Result<Server> {
+    let state = AppState {
+        prometheus: handle,
+        silences: monitor.silences(),
+        store: monitor.store(),
+        monitor,
+        auth: global.auth.clone(),
+        admin_auth: global.admin_auth(),
+        started: Utc::now(),
+        live_after: global.health.live_after(global.check_interval),
+        ready_after: global.health.ready_after(global.check_interval),
+    };
+    let app = Router::new()
+        .route("/", get(|| async { Html(DASHBOARD) }))
+        .route("/metrics", get(|State(s): State<AppState>| async move { s.prometheus.render() }))
+        .route("/health", get(|| async { "ok" }))
+        .route("/livez", get(livez))
+        .route("/readyz", get(readyz))
+        .route("/api/v1/status", get(status))
+        .route("/api/v1/summary", get(summary))
+        .route("/api/v1/clients/:chain/:client", get(client))
+        .route("/api/v1/check", post(trigger_check))
+        .route("/api/v1/events", get(events))
+        .route("/api/v1/ws", get(ws))
+        .route("/api/v1/silences", get(list_silences).post(add_silence))
+        .route("/api/v1/silences/:id", delete(remove_silence))
+        .layer(middleware::from_fn_with_state(state.clone(), authorize))
+        .with_state(state);
+
+    let tls = global.tls.as_ref().map(crate::tls::acceptor).transpose()?;
+    let listener = TcpListener::bind(addr).await.with_context(|| format!("failed to bind {}", addr))?;
+    Ok(Server { listener, app, tls })
+}
+
+impl Server {
+    pub async fn run(self) -> anyhow::Result<()> {
+        match self.tls {
+            Some(acceptor) => serve_tls(self.listener, self.app, acceptor).await,
+            None => Ok(axum::serve(self.listener, self.app).await?),
+        }
+    }
+}
+
+async fn serve_tls(listener: TcpListener, app: Router, acceptor: TlsAcceptor) -> anyhow::Result<()> {
+    loop {
+        let (tcp, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                // usually out of file descriptors, back off instead of spinning
+                tracing::warn!("failed to accept connection: {}", e);
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                continue;
+            }
+        };
+        let (acceptor, app) = (acceptor.clone(), app.clone());
+        tokio::spawn(async move {
+            let stream = match tokio::time::timeout(std::time::Duration::from_secs(10), acceptor.accept(tcp)).await {
+                Ok(Ok(stream)) => stream,
+                Ok(Err(e)) => return tracing::debug!(%peer, "tls handshake failed: {}", e),
+                Err(_) => return tracing::debug!(%peer, "tls handshake timed out"),
+            };
+            if let Err(e) = auto::Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(TokioIo::new(stream), TowerToHyperService::new(app))
+                .await
+            {
+                tracing::debug!(%peer, "connection error: {}", e);
+            }
+        });
+    }
+}
+
+enum Scope {
+    Open,
+    Metrics,
+    Api,
+    Admin,
+}
+
+// probes stay open so orchestrators can reach them without credentials
+fn scope(method: &Method, path: &str) -> Scope {
+    match path {
+        "/health" | "/livez" | "/readyz" => Scope::Open,
+        "/metrics" => Scope::Metrics,
+        "/api/v1/check" => Scope::Admin,
+        p if p.starts_with("/api/v1/silences") && method != Method::GET => Scope::Admin,
+        _ => Scope::Api,
+    }
+}
+
+async fn authorize(State(s): State<AppState>, req: Request, next: Next) -> Response {
+    let auth = match scope(req.method(), req.uri().path()) {
+        Scope::Open => None,
+        Scope::Metrics => s.auth.metrics.as_ref(),
+        Scope::Api => s.auth.api.as_ref(),
+        // mutating routes are never served without credentials
+        Scope::Admin => match &s.admin_auth {
+            Some(auth) => Some(auth),
+            None => {
+                return (
+                    StatusCode::FORBIDDEN,
+                    "set global.api_token or global.auth.api to enable changes over the api",
+                )
+                    .into_response()
+            }
+        },
+    };
+    match auth {
+        Some(auth) if !authorized(auth, req.headers()) => {
+            let challenge = match auth {
+                HttpAuth::Bearer { .. } => "Bearer",
+                HttpAuth::Basic { .. } => "Basic realm=\"ibc-monitor\"",
+            };
+            (StatusCode::UNAUTHORIZED, [(WWW_AUTHENTICATE, challenge)], "invalid credentials").into_response()
+        }
+        _ => next.run(req).await,
+    }
+}
+
+fn authorized(auth: &HttpAuth, headers: &HeaderMap) -> bool {
+    let header = headers.get(AUTHORIZATION).and_then(|v| v.to_str().ok()).unwrap_or_default();
+    let (given, expected) = match auth {
+        HttpAuth::Bearer { token } => (header.strip_prefix("Bearer ").map(|t| t.as_bytes().to_vec()), token.clone()),
+        HttpAuth::Basic { username, password } => (
+            header.strip_prefix("Basic ").and_then(|c| STANDARD.decode(c).ok()),
+            format!("{}:{}", username, password),
+        ),
+    };
+    // compare digests so the check does not leak how much of the credential matched
+    given.is_some_and(|given| Sha256::digest(given) == Sha256::digest(expected))
+}
+
+type ApiError = (StatusCode, String);
+
+fn internal(e: anyhow::Error) -> ApiError {
+    (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e))
+}
+
+// fails once the check loop has stopped making progress, e.g. a hung grpc call
+async fn livez(State(s): State<AppState>) -> Result<&'static str, ApiError> {
+    let last = s.store.last_progress().await.unwrap_or(s.started);
+    match Utc::now() - last {
+        age if age > s.live_after => Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("no check progress for {}s", age.num_seconds()),
+        )),
+        _ => Ok("ok"),
+    }
+}
+
+async fn readyz(State(s): State<AppState>) -> Result<&'static str, ApiError> {
+    let swept = s.store.last_sweep().await.ok_or_else(no_results)?;
+    match Utc::now() - swept {
+        age if age > s.ready_after => Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("last sweep completed {}s ago", age.num_seconds()),
+        )),
+        _ => Ok("ok"),
+    }
+}
+
+fn no_results() -> ApiError {
+    (StatusCode::SERVICE_UNAVAILABLE, "no check has completed yet".to_string())
+}
+
+#[derive(Serialize)]
+struct SummaryResponse {
+    timestamp: DateTime<Utc>,
+    summary: Summary,
+}
+
+async fn status(State(s): State<AppState>) -> Result<Json<CheckResult>, ApiError> {
+    s.store.latest().await.map(Json).ok_or_else(no_results)
+}
+
+async fn summary(State(s): State<AppState>) -> Result<Json<SummaryResponse>, ApiError> {
+    let latest = s.store.latest().await.ok_or_else(no_results)?;
+    Ok(Json(SummaryResponse {
+        timestamp: latest.timestamp,
+        summary: latest.summary,
+    }))
+}
+
+async fn client(
+    State(s): State<AppState>,
+    Path((chain, client)): Path<(String, String)>,
+) -> Result<Json<ClientDetail>, ApiError> {
+    s.store
+        .client(&chain, &client)
+        .await
+        .map(Json)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("no client {} on {}", client, chain)))
+}
+
+#[derive(Deserialize)]
+struct CheckQuery {
+    monitor: Option<String>,
+}
+
+async fn trigger_check(
+    State(s): State<AppState>,
+    Query(q): Query<CheckQuery>,
+) -> Result<Json<CheckResult>, ApiError> {
+    s.monitor.trigger(q.monitor.as_deref()).await.map(Json).ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("no monitor {}", q.monitor.unwrap_or_default()),
+        )
+    })
+}
+
+async fn events(State(s): State<AppState>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    // subscribers that fall behind skip the events they missed
+    let stream = BroadcastStream::new(s.store.subscribe()).filter_map(|event| {
+        let event = event.ok()?;
+        Event::default().event(event.name()).json_data(&event).ok().map(Ok)
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+async fn ws(State(s): State<AppState>, upgrade: WebSocketUpgrade) -> Response {
+    upgrade.on_upgrade(move |socket| stream_events(socket, s.store))
+}
+
+// reading answers pings and, after a close frame, flushes the reply until the connection
+// ends. incoming messages are otherwise ignored
+async fn stream_events(mut socket: WebSocket, store: ResultStore) {
+    let mut events = store.subscribe();
+    loop {
+        let event: StatusEvent = tokio::select! {
+            msg = socket.recv() => match msg {
+                Some(Ok(_)) => continue,
+                Some(Err(_)) | None => return,
+            },
+            event = events.recv() => match event {
+                Ok(e) => e,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            },
+        };
+        let Ok(text) = serde_json::to_string(&event) else { continue };
+        if socket.send(Message::Text(text)).await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn list_silences(State(s): State<AppState>) -> Result<Json<Vec<Silence>>, ApiError> {
+    s.silences.list().await.map(Json).map_err(internal)
+}
+
+async fn add_silence(
+    State(s): State<AppState>,
+    Json(req): Json<NewSilence>,
+) -> Result<(StatusCode, Json<Silence>), ApiError> {
+    s.silences
+        .add(req)
+        .await
+        .map(|silence| (StatusCode::CREATED, Json(silence)))
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("{:#}", e)))
+}
+
+async fn remove_silence(State(s): State<AppState>, Path(id): Path<String>) -> Result<StatusCode, ApiError> {
+    match s.silences.remove(&id).await.map_err(internal)? {
+        true => Ok(StatusCode::NO_CONTENT),
+        false => Err((StatusCode::NOT_FOUND, format!("no silence {}", id))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mutating_routes_are_admin() {
+        assert!(matches!(scope(&Method::POST, "/api/v1/check"), Scope::Admin));
+        assert!(matches!(scope(&Method::POST, "/api/v1/silences"), Scope::Admin));
+        assert!(matches!(scope(&Method::DELETE, "/api/v1/silences/abc"), Scope::Admin));
+        assert!(matches!(scope(&Method::GET, "/api/v1/silences"), Scope::Api));
+        assert!(matches!(scope(&Method::GET, "/livez"), Scope::Open));
+    }
+
+    #[test]
+    fn checks_bearer_and_basic_credentials() {
+        let mut headers = HeaderMap::new();
+        let bearer = HttpAuth::Bearer { token: "s3cret".to_string() };
+        assert!(!authorized(&bearer, &headers));
+        headers.insert(AUTHORIZATION, "Bearer s3cret".parse().unwrap());
+        assert!(authorized(&bearer, &headers));
+
+        let basic = HttpAuth::Basic {
+            username: "ops".to_string(),
+            password: "pw".to_string(),
+        };
+        assert!(!authorized(&basic, &headers));
+        headers.insert(AUTHORIZATION, format!("Basic {}", STANDARD.encode("ops:pw")).parse().unwrap());
+        assert!(authorized(&basic, &headers));
+    }
+}
@@ -0,0 +1,45 @@
+use crate::config::WebhookAuthConfig;
+use hmac::{Hmac, Mac};
+use reqwest::RequestBuilder;
+use sha2::Sha256;
+
+const DEFAULT_REPLAY_WINDOW: u64 = 300;
+
+#[derive(Clone, Default)]
+pub struct RequestAuth {
+    secret: Option<Vec<u8>>,
+    replay_window: u64,
+    headers: Vec<(String, String)>,
+}
+
+impl RequestAuth {
+    pub fn new(cfg: &WebhookAuthConfig) -> Self {
+        Self {
+            secret: cfg.secret.as_ref().map(|s| s.as_bytes().to_vec()),
+            replay_window: cfg.replay_window.unwrap_or(DEFAULT_REPLAY_WINDOW),
+            headers: cfg.headers.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        }
+    }
+
+    // signs every attempt with a fresh timestamp so retries from the outbox
+    // stay inside the receiver's replay window
+    pub fn apply(&self, mut req: RequestBuilder, body: &[u8]) -> RequestBuilder {
+        for (name, value) in &self.headers {
+            req = req.header(name, value);
+        }
+        let Some(secret) = &self.secret else { return req };
+
+        let timestamp = chrono::Utc::now().timestamp();
+        let expires = timestamp + self.replay_window as i64;
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("hmac accepts any key length");
+        mac.update(format!("{}.{}.", timestamp, expires).as_bytes());
+        mac.update(body);
+
+        req.header("x-ibc-monitor-timestamp", timestamp)
+            .header("x-ibc-monitor-expires", expires)
+            .header(
+                "x-ibc-monitor-signature",
+                format!("sha256={}", hex::encode(mac.finalize().into_bytes())),
+            )
+    }
+}
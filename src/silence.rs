@@ -0,0 +1,197 @@
+use crate::config::MonitorConfig;
+use crate::state::{load_json, save_json};
+use crate::types::ClientStatus;
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, sync::Arc};
+use tokio::sync::Mutex;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Silence {
+    pub id: String,
+    #[serde(flatten)]
+    pub matcher: Matcher,
+    pub reason: String,
+    pub created: DateTime<Utc>,
+    pub expires: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Matcher {
+    pub chain_id: Option<String>,
+    pub client_id: Option<String>,
+    pub counterparty: Option<String>,
+    pub name: Option<String>,
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewSilence {
+    #[serde(flatten)]
+    pub matcher: Matcher,
+    pub reason: String,
+    pub duration: String,
+}
+
+impl Matcher {
+    pub fn matches(&self, monitor: &MonitorConfig, client: &ClientStatus) -> bool {
+        let eq = |want: &Option<String>, have: Option<&str>| want.as_deref().is_none_or(|w| Some(w) == have);
+        eq(&self.chain_id, Some(&client.chain_id))
+            && eq(&self.client_id, Some(&client.client_id))
+            && eq(&self.counterparty, client.counterparty_chain_id.as_deref())
+            && eq(&self.status, Some(client.status.label()))
+            && self.name.as_deref().is_none_or(|p| glob_match(p, &monitor.name))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.chain_id.is_none()
+            && self.client_id.is_none()
+            && self.counterparty.is_none()
+            && self.name.is_none()
+            && self.status.is_none()
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Silences {
+    silences: Arc<Mutex<Vec<Silence>>>,
+    path: Option<PathBuf>,
+}
+
+impl Silences {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            silences: Arc::default(),
+            path,
+        }
+    }
+
+    // the file is re-read on every access so silences added from the cli
+    // are picked up by a running daemon
+    async fn with<T>(&self, write: bool, f: impl FnOnce(&mut Vec<Silence>) -> T) -> Result<T> {
+        let mut silences = self.silences.lock().await;
+        if let Some(path) = &self.path {
+            *silences = load_json(path)?;
+        }
+        let before = silences.len();
+        silences.retain(|s| s.expires > Utc::now());
+        let pruned = silences.len() != before;
+
+        let out = f(&mut silences);
+        if let Some(path) = self.path.as_ref().filter(|_| write || pruned) {
+            save_json(path, &*silences)?;
+        }
+        Ok(out)
+    }
+
+    pub async fn list(&self) -> Result<Vec<Silence>> {
+        self.with(false, |s| s.clone()).await
+    }
+
+    pub async fn add(&self, req: NewSilence) -> Result<Silence> {
+        if req.matcher.is_empty() {
+            bail!("a silence needs at least one of chain_id, client_id, counterparty, name or status");
+        }
+        let now = Utc::now();
+        let silence = Silence {
+            id: format!("{:x}", now.timestamp_micros()),
+            matcher: req.matcher,
+            reason: req.reason,
+            created: now,
+            expires: now
+                .checked_add_signed(parse_duration(&req.duration)?)
+                .ok_or_else(|| anyhow!("duration {} is too long", req.duration))?,
+        };
+        self.with(true, |s| s.push(silence.clone())).await?;
+        Ok(silence)
+    }
+
+    pub async fn remove(&self, id: &str) -> Result<bool> {
+        self.with(true, |s| {
+            let before = s.len();
+            s.retain(|x| x.id != id);
+            s.len() != before
+        })
+        .await
+    }
+
+    pub async fn matching(&self, monitor: &MonitorConfig, client: &ClientStatus) -> Option<Silence> {
+        match self.with(false, |s| s.iter().find(|x| x.matcher.matches(monitor, client)).cloned()).await {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::warn!("failed to read silences: {:#}", e);
+                None
+            }
+        }
+    }
+}
+
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (n, unit) = s.split_at(split);
+    let n: i64 = n.parse().map_err(|_| anyhow!("invalid duration: {}", s))?;
+    match unit {
+        "m" => Duration::try_minutes(n),
+        "h" | "" => Duration::try_hours(n),
+        "d" => Duration::try_days(n),
+        "w" => Duration::try_weeks(n),
+        _ => bail!("invalid duration unit in {}, expected m, h, d or w", s),
+    }
+    .ok_or_else(|| anyhow!("duration {} is too long", s))
+}
+
+pub fn glob_match(pattern: &str, s: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == s;
+    }
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !s.starts_with(first) || !s[first.len()..].ends_with(last) {
+        return false;
+    }
+    let mut rest = &s[first.len()..s.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_units() {
+        assert_eq!(parse_duration("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_duration("4").unwrap(), Duration::hours(4));
+        assert_eq!(parse_duration("2w").unwrap(), Duration::weeks(2));
+        assert!(parse_duration("3y").is_err());
+    }
+
+    #[test]
+    fn parse_duration_rejects_overflow() {
+        assert!(parse_duration("100000000000w").is_err());
+        assert!(parse_duration("100000000000000d").is_err());
+        assert!(parse_duration("99999999999999999999m").is_err());
+    }
+
+    #[tokio::test]
+    async fn add_rejects_expiry_past_the_end_of_time() {
+        let silences = Silences::default();
+        let req = NewSilence {
+            matcher: Matcher {
+                chain_id: Some("osmosis-1".to_string()),
+                ..Default::default()
+            },
+            reason: "test".to_string(),
+            duration: "100000000d".to_string(),
+        };
+        assert!(silences.add(req).await.is_err());
+        assert!(silences.list().await.unwrap().is_empty());
+    }
+}
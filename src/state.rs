@@ -1,23 +1,224 @@
-use crate::types::Status;
-use std::{collections::HashMap, sync::Arc};
+use crate::config::{ConfirmConfig, FlapConfig};
+use crate::types::{Status, Suppression};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::{path::{Path, PathBuf}, sync::Arc};
 use tokio::sync::Mutex;
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transition {
+    pub previous: Option<Status>,
+    pub degraded_since: Option<DateTime<Utc>>,
+}
+
+pub struct Observation {
+    pub transition: Option<Transition>,
+    pub suppressed: Option<Suppression>,
+}
+
+pub struct Reminder {
+    pub count: u32,
+    pub since: DateTime<Utc>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Entry {
+    status: Status,
+    since: DateTime<Utc>,
+    degraded_since: Option<DateTime<Utc>>,
+    last_notified: DateTime<Utc>,
+    reminders: u32,
+    #[serde(default)]
+    milestones: BTreeSet<u64>,
+    #[serde(default)]
+    pending: Option<(String, u32)>,
+    #[serde(default)]
+    history: Vec<DateTime<Utc>>,
+    #[serde(default)]
+    unannounced: Option<Transition>,
+}
+
 #[derive(Clone, Default)]
-pub struct StateTracker(Arc<Mutex<HashMap<String, Status>>>);
+pub struct StateTracker {
+    states: Arc<Mutex<HashMap<String, Entry>>>,
+    path: Option<PathBuf>,
+    confirm: ConfirmConfig,
+    flap: Option<FlapConfig>,
+}
 
 impl StateTracker {
-    pub fn new() -> Self {
-        Self::default()
+    pub fn load(path: Option<PathBuf>, confirm: ConfirmConfig, flap: Option<FlapConfig>) -> Result<Self> {
+        let states = match &path {
+            Some(p) => load_json(p)?,
+            None => HashMap::new(),
+        };
+        Ok(Self {
+            states: Arc::new(Mutex::new(states)),
+            path,
+            confirm,
+            flap,
+        })
+    }
+
+    pub async fn persist(&self) {
+        let Some(path) = &self.path else { return };
+        if let Err(e) = save_json(path, &*self.states.lock().await) {
+            tracing::warn!("failed to persist state: {:#}", e);
+        }
+    }
+
+    pub async fn forget(&self, key: &str) {
+        self.states.lock().await.remove(key);
     }
 
-    pub async fn has_changed(&self, key: &str, status: &Status) -> bool {
-        let mut states = self.0.lock().await;
-        match states.get(key) {
-            Some(prev) if std::mem::discriminant(prev) == std::mem::discriminant(status) => false,
-            _ => {
-                states.insert(key.into(), status.clone());
-                true
+    pub async fn observe(&self, key: &str, status: &Status) -> Observation {
+        let mut states = self.states.lock().await;
+        let now = Utc::now();
+
+        let Some(entry) = states.get_mut(key) else {
+            states.insert(
+                key.into(),
+                Entry {
+                    status: status.clone(),
+                    since: now,
+                    degraded_since: (!matches!(status, Status::Healthy { .. })).then_some(now),
+                    last_notified: now,
+                    reminders: 0,
+                    milestones: BTreeSet::new(),
+                    pending: None,
+                    history: Vec::new(),
+                    unannounced: None,
+                },
+            );
+            return Observation {
+                transition: Some(Transition { previous: None, degraded_since: None }),
+                suppressed: None,
+            };
+        };
+
+        if let Some(flap) = &self.flap {
+            entry.history.retain(|t| now - *t < Duration::minutes(flap.window as i64));
+        }
+        let flapping = |entry: &Entry| {
+            self.flap
+                .as_ref()
+                .filter(|f| entry.history.len() > f.max_transitions)
+                .map(|_| Suppression::Flapping { transitions: entry.history.len() })
+        };
+
+        if std::mem::discriminant(&entry.status) == std::mem::discriminant(status) {
+            entry.pending = None;
+            let suppressed = flapping(entry);
+            // a change hidden while flapping is announced once the client settles
+            if suppressed.is_none() {
+                if let Some(t) = entry.unannounced.take() {
+                    let settled = t.previous.as_ref().map(std::mem::discriminant) != Some(std::mem::discriminant(status));
+                    if settled {
+                        entry.last_notified = now;
+                        return Observation { transition: Some(t), suppressed: None };
+                    }
+                }
             }
+            return Observation { transition: None, suppressed };
+        }
+
+        let required = self.confirm.required(status);
+        let confirmations = match &entry.pending {
+            Some((label, n)) if label == status.label() => n + 1,
+            _ => 1,
+        };
+        if confirmations < required {
+            entry.pending = Some((status.label().to_string(), confirmations));
+            return Observation {
+                transition: None,
+                suppressed: Some(Suppression::Pending {
+                    status: status.label().to_string(),
+                    confirmations,
+                    required,
+                }),
+            };
         }
+
+        let mut transition = Transition {
+            previous: Some(entry.status.clone()),
+            degraded_since: entry.degraded_since,
+        };
+        entry.status = status.clone();
+        entry.since = now;
+        entry.degraded_since = match status {
+            Status::Healthy { .. } => None,
+            _ => entry.degraded_since.or(Some(now)),
+        };
+        entry.last_notified = now;
+        entry.reminders = 0;
+        entry.pending = None;
+        entry.history.push(now);
+
+        let suppressed = flapping(entry);
+        if suppressed.is_some() {
+            entry.unannounced.get_or_insert(transition.clone());
+            return Observation { transition: Some(transition), suppressed };
+        }
+        if let Some(t) = entry.unannounced.take() {
+            // flapping ended on the state that was last announced
+            if t.previous.as_ref().map(std::mem::discriminant) == Some(std::mem::discriminant(status)) {
+                return Observation { transition: None, suppressed: None };
+            }
+            transition = t;
+        }
+        Observation { transition: Some(transition), suppressed: None }
+    }
+
+    // returns the most urgent countdown milestone newly crossed since the last call,
+    // milestones are re-armed once the client is updated above them again
+    pub async fn milestone(&self, key: &str, hours_left: f64, milestones: &[u64]) -> Option<u64> {
+        let mut states = self.states.lock().await;
+        let entry = states.get_mut(key)?;
+        entry.milestones.retain(|m| hours_left < *m as f64);
+
+        let crossed = milestones
+            .iter()
+            .filter(|m| hours_left < **m as f64 && !entry.milestones.contains(m))
+            .copied()
+            .collect::<Vec<_>>();
+        entry.milestones.extend(&crossed);
+
+        let most_urgent = crossed.into_iter().min()?;
+        entry.last_notified = Utc::now();
+        Some(most_urgent)
+    }
+
+    pub async fn reminder(&self, key: &str, every: Duration) -> Option<Reminder> {
+        let mut states = self.states.lock().await;
+        let entry = states.get_mut(key)?;
+        let now = Utc::now();
+        if now - entry.last_notified < every {
+            return None;
+        }
+        entry.last_notified = now;
+        entry.reminders += 1;
+        Some(Reminder {
+            count: entry.reminders,
+            since: entry.since,
+        })
+    }
+}
+
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+    match std::fs::read_to_string(path) {
+        Ok(s) => serde_json::from_str(&s).with_context(|| format!("invalid state file {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(e).with_context(|| format!("reading {}", path.display())),
+    }
+}
+
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
     }
-}
\ No newline at end of file
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(value)?)?;
+    std::fs::rename(&tmp, path).with_context(|| format!("writing {}", path.display()))
+}
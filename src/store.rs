@@ -0,0 +1,203 @@
+use crate::types::{CheckResult, ClientStatus, Status, Summary, Suppression};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::sync::{broadcast, RwLock};
+
+// a day of checks at the default interval
+const HISTORY_LEN: usize = 288;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEntry {
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub status: Status,
+    pub suppressed: Option<Suppression>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientDetail {
+    pub monitor: String,
+    pub client: ClientStatus,
+    pub history: Vec<HistoryEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StatusEvent {
+    Sweep(CheckResult),
+    Transition {
+        timestamp: DateTime<Utc>,
+        monitor: String,
+        client: Box<ClientStatus>,
+        previous: Option<Status>,
+    },
+}
+
+impl StatusEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StatusEvent::Sweep(_) => "sweep",
+            StatusEvent::Transition { .. } => "transition",
+        }
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    progress: Option<DateTime<Utc>>,
+    swept: Option<DateTime<Utc>>,
+    latest: Option<CheckResult>,
+    history: HashMap<(String, String), VecDeque<HistoryEntry>>,
+}
+
+impl Inner {
+    fn record_history(&mut self, result: &CheckResult) {
+        for client in result.monitors.iter().flat_map(|m| &m.clients) {
+            let history = self
+                .history
+                .entry((client.chain_id.clone(), client.id().to_string()))
+                .or_default();
+            if history.len() == HISTORY_LEN {
+                history.pop_front();
+            }
+            history.push_back(HistoryEntry {
+                timestamp: result.timestamp,
+                status: client.status.clone(),
+                suppressed: client.suppressed.clone(),
+            });
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ResultStore {
+    inner: Arc<RwLock<Inner>>,
+    events: broadcast::Sender<StatusEvent>,
+}
+
+impl Default for ResultStore {
+    fn default() -> Self {
+        Self {
+            inner: Arc::default(),
+            events: broadcast::channel(256).0,
+        }
+    }
+}
+
+impl ResultStore {
+    pub fn subscribe(&self) -> broadcast::Receiver<StatusEvent> {
+        self.events.subscribe()
+    }
+
+    // sending only fails when nobody is subscribed
+    pub fn publish(&self, event: StatusEvent) {
+        let _ = self.events.send(event);
+    }
+
+    // called by the check loop whenever a monitor finishes, so a hung sweep shows up in liveness
+    pub async fn progress(&self) {
+        self.inner.write().await.progress = Some(Utc::now());
+    }
+
+    pub async fn last_progress(&self) -> Option<DateTime<Utc>> {
+        self.inner.read().await.progress
+    }
+
+    pub async fn update(&self, result: &CheckResult) {
+        let mut inner = self.inner.write().await;
+        inner.record_history(result);
+        inner.progress = Some(Utc::now());
+        inner.swept = Some(result.timestamp);
+        inner.latest = Some(result.clone());
+        self.publish(StatusEvent::Sweep(result.clone()));
+    }
+
+    // completion time of the last full sweep, partial checks folded in by `merge` do not count
+    pub async fn last_sweep(&self) -> Option<DateTime<Utc>> {
+        self.inner.read().await.swept
+    }
+
+    // folds a check of some monitors into the latest sweep, which keeps its timestamp.
+    // before the first sweep the partial result is all there is to show
+    pub async fn merge(&self, result: &CheckResult) {
+        let mut inner = self.inner.write().await;
+        inner.record_history(result);
+        let latest = inner.latest.get_or_insert_with(|| result.clone());
+        for monitor in &result.monitors {
+            match latest.monitors.iter_mut().find(|m| m.name == monitor.name) {
+                Some(m) => *m = monitor.clone(),
+                None => latest.monitors.push(monitor.clone()),
+            }
+        }
+        let clients: Vec<_> = latest.monitors.iter().flat_map(|m| m.clients.clone()).collect();
+        latest.summary = Summary::from_statuses(&clients);
+    }
+
+    pub async fn latest(&self) -> Option<CheckResult> {
+        self.inner.read().await.latest.clone()
+    }
+
+    pub async fn client(&self, chain: &str, id: &str) -> Option<ClientDetail> {
+        let inner = self.inner.read().await;
+        let (monitor, client) = inner
+            .latest
+            .as_ref()?
+            .monitors
+            .iter()
+            .flat_map(|m| m.clients.iter().map(move |c| (m, c)))
+            .find(|(_, c)| c.chain_id == chain && c.id() == id)?;
+        Some(ClientDetail {
+            monitor: monitor.name.clone(),
+            client: client.clone(),
+            history: inner
+                .history
+                .get(&(chain.to_string(), id.to_string()))
+                .map(|h| h.iter().cloned().collect())
+                .unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MonitorResult;
+
+    fn result(monitors: &[&str]) -> CheckResult {
+        CheckResult {
+            timestamp: Utc::now(),
+            monitors: monitors
+                .iter()
+                .map(|name| MonitorResult {
+                    name: name.to_string(),
+                    clients: Vec::new(),
+                })
+                .collect(),
+            summary: Summary::from_statuses(&[]),
+        }
+    }
+
+    #[tokio::test]
+    async fn merge_before_first_sweep_seeds_latest() {
+        let store = ResultStore::default();
+        store.merge(&result(&["a"])).await;
+        let latest = store.latest().await.expect("merged result is served");
+        assert_eq!(latest.monitors.len(), 1);
+        assert!(store.last_sweep().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn merge_replaces_monitors_by_name() {
+        let store = ResultStore::default();
+        let sweep = result(&["a", "b"]);
+        store.update(&sweep).await;
+        store.merge(&result(&["b", "c"])).await;
+        let latest = store.latest().await.unwrap();
+        let names: Vec<_> = latest.monitors.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["a", "b", "c"]);
+        assert_eq!(latest.timestamp, sweep.timestamp);
+        assert_eq!(store.last_sweep().await, Some(sweep.timestamp));
+    }
+}
@@ -0,0 +1,281 @@
+use crate::config::{OtelConfig, OtelProtocol};
+use anyhow::{Context, Result};
+use metrics::{Counter, CounterFn, Gauge, GaugeFn, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder, SharedString, Unit};
+use opentelemetry::{metrics::Meter, trace::TracerProvider as _, KeyValue};
+use opentelemetry_otlp::{MetricExporter, SpanExporter, WithExportConfig, WithHttpConfig, WithTonicConfig};
+use opentelemetry_sdk::{
+    metrics::{PeriodicReader, SdkMeterProvider},
+    runtime,
+    trace::{Tracer, TracerProvider},
+    Resource,
+};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tonic::metadata::{MetadataKey, MetadataMap};
+use tracing_opentelemetry::OpenTelemetryLayer;
+
+#[derive(Default)]
+pub struct Telemetry {
+    tracer: Option<TracerProvider>,
+    meter: Option<SdkMeterProvider>,
+}
+
+impl Telemetry {
+    pub fn new(cfg: Option<&OtelConfig>) -> Result<Self> {
+        let Some(cfg) = cfg else { return Ok(Self::default()) };
+        let resource = Resource::new([KeyValue::new(
+            "service.name",
+            cfg.service_name.clone().unwrap_or_else(|| "ibc-monitor".to_string()),
+        )]);
+
+        let tracer = cfg
+            .traces
+            .then(|| -> Result<_> {
+                let exporter = match cfg.protocol {
+                    OtelProtocol::Grpc => SpanExporter::builder()
+                        .with_tonic()
+                        .with_endpoint(endpoint(cfg, ""))
+                        .with_metadata(metadata(cfg)?)
+                        .build()?,
+                    OtelProtocol::Http => SpanExporter::builder()
+                        .with_http()
+                        .with_endpoint(endpoint(cfg, "/v1/traces"))
+                        .with_headers(cfg.headers.clone().into_iter().collect())
+                        .build()?,
+                };
+                Ok(TracerProvider::builder()
+                    .with_batch_exporter(exporter, runtime::Tokio)
+                    .with_resource(resource.clone())
+                    .build())
+            })
+            .transpose()
+            .context("failed to set up otlp trace exporter")?;
+
+        let meter = cfg
+            .metrics
+            .then(|| -> Result<_> {
+                let exporter = match cfg.protocol {
+                    OtelProtocol::Grpc => MetricExporter::builder()
+                        .with_tonic()
+                        .with_endpoint(endpoint(cfg, ""))
+                        .with_metadata(metadata(cfg)?)
+                        .build()?,
+                    OtelProtocol::Http => MetricExporter::builder()
+                        .with_http()
+                        .with_endpoint(endpoint(cfg, "/v1/metrics"))
+                        .with_headers(cfg.headers.clone().into_iter().collect())
+                        .build()?,
+                };
+                let reader = PeriodicReader::builder(exporter, runtime::Tokio)
+                    .with_interval(Duration::from_secs(cfg.interval))
+                    .build();
+                Ok(SdkMeterProvider::builder().with_reader(reader).with_resource(resource).build())
+            })
+            .transpose()
+            .context("failed to set up otlp metric exporter")?;
+
+        Ok(Self { tracer, meter })
+    }
+
+    pub fn layer<S>(&self) -> Option<OpenTelemetryLayer<S, Tracer>>
+    where
+        S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+    {
+        let tracer = self.tracer.as_ref()?.tracer("ibc-monitor");
+        Some(tracing_opentelemetry::layer().with_tracer(tracer))
+    }
+
+    pub fn recorder(&self) -> Option<OtelRecorder> {
+        let meter = self.meter.as_ref()?;
+        Some(OtelRecorder {
+            meter: opentelemetry::metrics::MeterProvider::meter(meter, "ibc-monitor"),
+            descriptions: Mutex::default(),
+            counters: Mutex::default(),
+            gauges: Mutex::default(),
+            histograms: Mutex::default(),
+        })
+    }
+
+    // flushes buffered spans and a final metric export, needed for one-shot checks
+    pub fn shutdown(&self) {
+        if let Some(t) = &self.tracer {
+            if let Err(e) = t.shutdown() {
+                tracing::warn!("failed to flush traces: {}", e);
+            }
+        }
+        if let Some(m) = &self.meter {
+            if let Err(e) = m.shutdown() {
+                tracing::warn!("failed to flush metrics: {}", e);
+            }
+        }
+    }
+}
+
+fn endpoint(cfg: &OtelConfig, path: &str) -> String {
+    let base = cfg.endpoint.clone().unwrap_or_else(|| {
+        match cfg.protocol {
+            OtelProtocol::Grpc => "http://localhost:4317",
+            OtelProtocol::Http => "http://localhost:4318",
+        }
+        .to_string()
+    });
+    format!("{}{}", base.trim_end_matches('/'), path)
+}
+
+fn metadata(cfg: &OtelConfig) -> Result<MetadataMap> {
+    let mut map = MetadataMap::new();
+    for (k, v) in &cfg.headers {
+        map.insert(
+            MetadataKey::from_bytes(k.to_lowercase().as_bytes()).with_context(|| format!("invalid header {}", k))?,
+            v.parse().with_context(|| format!("invalid value for header {}", k))?,
+        );
+    }
+    Ok(map)
+}
+
+// forwards the `metrics` facade to the otel meter so both exporters see the same metric set
+pub struct OtelRecorder {
+    meter: Meter,
+    descriptions: Mutex<HashMap<String, SharedString>>,
+    counters: Mutex<HashMap<Key, Arc<OtelCounter>>>,
+    gauges: Mutex<HashMap<Key, Arc<OtelGauge>>>,
+    histograms: Mutex<HashMap<Key, Arc<OtelHistogram>>>,
+}
+
+impl OtelRecorder {
+    fn describe(&self, key: KeyName, description: SharedString) {
+        self.descriptions.lock().unwrap().insert(key.as_str().to_string(), description);
+    }
+
+    fn description(&self, key: &Key) -> String {
+        self.descriptions
+            .lock()
+            .unwrap()
+            .get(key.name())
+            .map(|d| d.to_string())
+            .unwrap_or_default()
+    }
+}
+
+fn attributes(key: &Key) -> Vec<KeyValue> {
+    key.labels()
+        .map(|l| KeyValue::new(l.key().to_string(), l.value().to_string()))
+        .collect()
+}
+
+struct OtelCounter {
+    counter: opentelemetry::metrics::Counter<u64>,
+    attributes: Vec<KeyValue>,
+    value: AtomicU64,
+}
+
+impl CounterFn for OtelCounter {
+    fn increment(&self, value: u64) {
+        self.value.fetch_add(value, Ordering::Relaxed);
+        self.counter.add(value, &self.attributes);
+    }
+
+    fn absolute(&self, value: u64) {
+        let prev = self.value.fetch_max(value, Ordering::Relaxed);
+        if value > prev {
+            self.counter.add(value - prev, &self.attributes);
+        }
+    }
+}
+
+struct OtelGauge {
+    gauge: opentelemetry::metrics::Gauge<f64>,
+    attributes: Vec<KeyValue>,
+    value: Mutex<f64>,
+}
+
+impl GaugeFn for OtelGauge {
+    fn increment(&self, value: f64) {
+        let mut v = self.value.lock().unwrap();
+        *v += value;
+        self.gauge.record(*v, &self.attributes);
+    }
+
+    fn decrement(&self, value: f64) {
+        self.increment(-value);
+    }
+
+    fn set(&self, value: f64) {
+        *self.value.lock().unwrap() = value;
+        self.gauge.record(value, &self.attributes);
+    }
+}
+
+struct OtelHistogram {
+    histogram: opentelemetry::metrics::Histogram<f64>,
+    attributes: Vec<KeyValue>,
+}
+
+impl HistogramFn for OtelHistogram {
+    fn record(&self, value: f64) {
+        self.histogram.record(value, &self.attributes);
+    }
+}
+
+impl Recorder for OtelRecorder {
+    fn describe_counter(&self, key: KeyName, _: Option<Unit>, description: SharedString) {
+        self.describe(key, description);
+    }
+
+    fn describe_gauge(&self, key: KeyName, _: Option<Unit>, description: SharedString) {
+        self.describe(key, description);
+    }
+
+    fn describe_histogram(&self, key: KeyName, _: Option<Unit>, description: SharedString) {
+        self.describe(key, description);
+    }
+
+    fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+        let handle = self.counters.lock().unwrap().entry(key.clone()).or_insert_with(|| {
+            Arc::new(OtelCounter {
+                counter: self
+                    .meter
+                    .u64_counter(key.name().to_string())
+                    .with_description(self.description(key))
+                    .build(),
+                attributes: attributes(key),
+                value: AtomicU64::new(0),
+            })
+        })
+        .clone();
+        Counter::from_arc(handle)
+    }
+
+    fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+        let handle = self.gauges.lock().unwrap().entry(key.clone()).or_insert_with(|| {
+            Arc::new(OtelGauge {
+                gauge: self
+                    .meter
+                    .f64_gauge(key.name().to_string())
+                    .with_description(self.description(key))
+                    .build(),
+                attributes: attributes(key),
+                value: Mutex::new(0.0),
+            })
+        })
+        .clone();
+        Gauge::from_arc(handle)
+    }
+
+    fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+        let handle = self.histograms.lock().unwrap().entry(key.clone()).or_insert_with(|| {
+            Arc::new(OtelHistogram {
+                histogram: self
+                    .meter
+                    .f64_histogram(key.name().to_string())
+                    .with_description(self.description(key))
+                    .build(),
+                attributes: attributes(key),
+            })
+        })
+        .clone();
+        Histogram::from_arc(handle)
+    }
+}
@@ -0,0 +1,136 @@
+use crate::config::{TemplateConfig, TemplateEscape, WebhookAuthConfig};
+use crate::digest::Digest;
+use crate::group::AlertGroup;
+use crate::notify::{escape_html, Alert};
+use crate::signing::RequestAuth;
+use anyhow::{anyhow, Context, Result};
+use handlebars::{handlebars_helper, Handlebars};
+use reqwest::Method;
+use std::sync::Arc;
+use std::time::Duration;
+
+handlebars_helper!(json: |v: Json| serde_json::to_string(v).unwrap_or_default());
+
+#[derive(Clone)]
+pub struct TemplateClient {
+    client: reqwest::Client,
+    templates: Arc<Handlebars<'static>>,
+    headers: Vec<String>,
+    digest: bool,
+    group: bool,
+    auth: RequestAuth,
+}
+
+impl TemplateClient {
+    pub fn new(cfg: &TemplateConfig) -> Result<Self> {
+        let mut templates = Handlebars::new();
+        templates.register_helper("json", Box::new(json));
+        match cfg.escape {
+            TemplateEscape::Json => templates.register_escape_fn(escape_json),
+            TemplateEscape::Html => templates.register_escape_fn(escape_html),
+            TemplateEscape::None => templates.register_escape_fn(handlebars::no_escape),
+        }
+
+        templates
+            .register_template_string("url", &cfg.url)
+            .context("invalid url template")?;
+        templates
+            .register_template_string("method", cfg.method.as_deref().unwrap_or("POST"))
+            .context("invalid method template")?;
+        templates
+            .register_template_string("body", &cfg.body)
+            .context("invalid body template")?;
+        if let Some(body) = &cfg.digest_body {
+            templates
+                .register_template_string("digest", body)
+                .context("invalid digest body template")?;
+        }
+        if let Some(body) = &cfg.group_body {
+            templates
+                .register_template_string("group", body)
+                .context("invalid group body template")?;
+        }
+        for (name, value) in &cfg.headers {
+            templates
+                .register_template_string(&format!("header:{}", name), value)
+                .with_context(|| format!("invalid template for header {}", name))?;
+        }
+
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap(),
+            templates: Arc::new(templates),
+            headers: cfg.headers.keys().cloned().collect(),
+            digest: cfg.digest_body.is_some(),
+            group: cfg.group_body.is_some(),
+            auth: RequestAuth::new(&WebhookAuthConfig {
+                secret: cfg.secret.clone(),
+                replay_window: cfg.replay_window,
+                headers: Default::default(),
+            }),
+        })
+    }
+
+    pub async fn send_alert(&self, alert: &Alert) -> Result<()> {
+        tracing::info!(
+            chain = %alert.client.chain_id,
+            client_id = %alert.client.client_id,
+            label = %alert.client.status.label(),
+            "template webhook"
+        );
+        self.send("body", &alert.context()).await
+    }
+
+    // without a group_body template alerts are not grouped but delivered and retried one by one
+    pub fn groups(&self) -> bool {
+        self.group
+    }
+
+    pub async fn send_group(&self, group: &AlertGroup) -> Result<()> {
+        self.send("group", &group.context()).await
+    }
+
+    // without a digest_body template the notifier is left out of digests
+    pub fn digests(&self) -> bool {
+        self.digest
+    }
+
+    pub async fn send_digest(&self, digest: &Digest) -> Result<()> {
+        let ctx = serde_json::json!({
+            "title": digest.title(),
+            "digest": digest,
+            "text": digest.message().text,
+            "timestamp": chrono::Utc::now(),
+        });
+        self.send("digest", &ctx).await
+    }
+
+    async fn send(&self, body: &str, ctx: &serde_json::Value) -> Result<()> {
+        let render = |name: &str| {
+            self.templates
+                .render(name, ctx)
+                .with_context(|| format!("rendering {} template", name))
+        };
+
+        let method = Method::from_bytes(render("method")?.trim().to_uppercase().as_bytes())?;
+        let body = render(body)?;
+        let mut req = self.auth.apply(self.client.request(method, render("url")?.trim()), body.as_bytes());
+        for name in &self.headers {
+            req = req.header(name, render(&format!("header:{}", name))?);
+        }
+        req = req.body(body);
+
+        let res = req.send().await?;
+        if !res.status().is_success() {
+            return Err(anyhow!("template webhook {}: {}", res.status(), res.text().await?));
+        }
+        Ok(())
+    }
+}
+
+fn escape_json(s: &str) -> String {
+    let quoted = serde_json::to_string(s).unwrap_or_default();
+    quoted[1..quoted.len() - 1].to_string()
+}
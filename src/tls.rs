@@ -0,0 +1,81 @@
+use crate::config::TlsConfig;
+use anyhow::{anyhow, Context, Result};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio_rustls::rustls::{
+    crypto::ring,
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+    ServerConfig,
+};
+use tokio_rustls::TlsAcceptor;
+
+// serves whatever certificate was loaded last, so rotations apply without dropping connections
+#[derive(Debug)]
+struct Reloadable(RwLock<Arc<CertifiedKey>>);
+
+impl ResolvesServerCert for Reloadable {
+    fn resolve(&self, _: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.0.read().unwrap().clone())
+    }
+}
+
+pub fn acceptor(cfg: &TlsConfig) -> Result<TlsAcceptor> {
+    let resolver = Arc::new(Reloadable(RwLock::new(Arc::new(load(cfg)?))));
+    let mut config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_cert_resolver(resolver.clone());
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    tokio::spawn(watch(cfg.clone(), resolver));
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+fn load(cfg: &TlsConfig) -> Result<CertifiedKey> {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(open(&cfg.cert)?))
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("invalid certificate {}", cfg.cert.display()))?;
+    anyhow::ensure!(!certs.is_empty(), "no certificates in {}", cfg.cert.display());
+    let key = rustls_pemfile::private_key(&mut BufReader::new(open(&cfg.key)?))
+        .with_context(|| format!("invalid key {}", cfg.key.display()))?
+        .ok_or_else(|| anyhow!("no private key in {}", cfg.key.display()))?;
+    let key = ring::sign::any_supported_type(&key).with_context(|| format!("unsupported key {}", cfg.key.display()))?;
+    let key = CertifiedKey::new(certs, key);
+    key.keys_match()
+        .with_context(|| format!("{} does not match {}", cfg.key.display(), cfg.cert.display()))?;
+    Ok(key)
+}
+
+fn open(path: &Path) -> Result<File> {
+    File::open(path).with_context(|| format!("failed to open {}", path.display()))
+}
+
+fn modified(cfg: &TlsConfig) -> Option<(SystemTime, SystemTime)> {
+    let mtime = |p: &Path| std::fs::metadata(p).and_then(|m| m.modified()).ok();
+    Some((mtime(&cfg.cert)?, mtime(&cfg.key)?))
+}
+
+async fn watch(cfg: TlsConfig, resolver: Arc<Reloadable>) {
+    let mut seen = modified(&cfg);
+    let mut ticker = tokio::time::interval(Duration::from_secs(cfg.reload_interval.max(1)));
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        let current = modified(&cfg);
+        if current.is_none() || current == seen {
+            continue;
+        }
+        // cert and key are usually replaced one after the other, a mismatched pair is retried next tick
+        match load(&cfg) {
+            Ok(key) => {
+                *resolver.0.write().unwrap() = Arc::new(key);
+                seen = current;
+                tracing::info!(cert = %cfg.cert.display(), "reloaded tls certificate");
+            }
+            Err(e) => tracing::warn!("failed to reload tls certificate, keeping the previous one: {:#}", e),
+        }
+    }
+}
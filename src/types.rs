@@ -1,17 +1,61 @@
 use chrono::{DateTime, Duration, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClientStatus {
     pub chain_id: String,
     pub client_id: String,
     pub status: Status,
     pub last_update: Option<DateTime<Utc>>,
+    #[serde(serialize_with = "serialize_secs", deserialize_with = "deserialize_secs")]
     pub trusting_period: Duration,
+    #[serde(serialize_with = "serialize_secs", deserialize_with = "deserialize_secs")]
     pub unbonding_period: Duration,
     pub latest_height: Option<(u64, u64)>,
     pub counterparty_chain_id: Option<String>,
     pub channel: String,
+    #[serde(default)]
+    pub suppressed: Option<Suppression>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Suppression {
+    Pending { status: String, confirmations: u32, required: u32 },
+    Flapping { transitions: usize },
+    Silenced { id: String, reason: String, expires: DateTime<Utc> },
+}
+
+impl ClientStatus {
+    // clients that failed discovery have no id yet, fall back to the channel
+    pub fn id(&self) -> &str {
+        match self.client_id.as_str() {
+            "unknown" => &self.channel,
+            id => id,
+        }
+    }
+}
+
+impl Suppression {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Suppression::Pending { .. } => "pending",
+            Suppression::Flapping { .. } => "flapping",
+            Suppression::Silenced { .. } => "silenced",
+        }
+    }
+}
+
+impl std::fmt::Display for Suppression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Suppression::Pending { status, confirmations, required } => {
+                write!(f, "pending {} {}/{}", status, confirmations, required)
+            }
+            Suppression::Flapping { transitions } => write!(f, "flapping {}x", transitions),
+            Suppression::Silenced { expires, .. } => write!(f, "silenced until {}", expires.format("%Y-%m-%d %H:%M")),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -24,20 +68,53 @@ pub enum Status {
     Error { reason: String },
 }
 
+impl Status {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Status::Healthy { .. } => "healthy",
+            Status::Warning { .. } => "warning",
+            Status::Critical { .. } => "critical",
+            Status::Expired { .. } => "expired",
+            Status::Error { .. } => "error",
+        }
+    }
 
-#[derive(Debug, Clone)]
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            Status::Healthy { .. } => "✅",
+            Status::Warning { .. } => "⚠️",
+            Status::Critical { .. } => "🚨",
+            Status::Expired { .. } => "❌",
+            Status::Error { .. } => "❗",
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Status::Healthy { hours_until_expiry: h }
+            | Status::Warning { hours_until_expiry: h }
+            | Status::Critical { hours_until_expiry: h } => format!("{:.1}h left", h),
+            Status::Expired { hours_since_expiry: h } => format!("{:.1}h ago", h),
+            Status::Error { reason } => reason.clone(),
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CheckResult {
     pub timestamp: DateTime<Utc>,
     pub monitors: Vec<MonitorResult>,
     pub summary: Summary,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitorResult {
+    pub name: String,
     pub clients: Vec<ClientStatus>,
 }
 
-#[derive(Debug, Clone, Default, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Summary {
     pub total: usize,
     pub healthy: usize,
@@ -49,9 +126,11 @@ pub struct Summary {
 
 impl Summary {
     pub fn from_statuses(statuses: &[ClientStatus]) -> Self {
-        let mut s = Self::default();
-        s.total = statuses.len();
-        
+        let mut s = Self {
+            total: statuses.len(),
+            ..Self::default()
+        };
+
         for status in statuses {
             match &status.status {
                 Status::Healthy { .. } => s.healthy += 1,
@@ -63,4 +142,20 @@ impl Summary {
         }
         s
     }
-}
\ No newline at end of file
+}
+
+pub fn format_duration(d: Duration) -> String {
+    match (d.num_days(), d.num_hours() % 24, d.num_minutes() % 60) {
+        (0, 0, m) => format!("{}m", m),
+        (0, h, m) => format!("{}h {}m", h, m),
+        (days, h, _) => format!("{}d {}h", days, h),
+    }
+}
+
+fn serialize_secs<S: Serializer>(d: &Duration, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_i64(d.num_seconds())
+}
+
+fn deserialize_secs<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
+    i64::deserialize(d).map(Duration::seconds)
+}
@@ -1,3 +1,6 @@
+use crate::config::WebhookAuthConfig;
+use crate::notify::{Alert, AlertKind, Message};
+use crate::signing::RequestAuth;
 use crate::types::Status;
 use anyhow::{anyhow, Result};
 use serde::Serialize;
@@ -7,6 +10,7 @@ use std::time::Duration;
 pub struct WebhookClient {
     client: reqwest::Client,
     url: Option<String>,
+    auth: RequestAuth,
 }
 
 #[derive(Serialize)]
@@ -15,64 +19,58 @@ struct SlackMessage<'a> {
     attachments: [Attachment<'a>; 1],
 }
 
+#[derive(Serialize)]
+struct SlackText {
+    text: String,
+}
+
 #[derive(Serialize)]
 struct Attachment<'a> {
     color: &'a str,
-    fields: [Field<'a>; 4],
+    fields: Vec<Field>,
     footer: &'a str,
     ts: i64,
 }
 
 #[derive(Serialize)]
-struct Field<'a> {
-    title: &'a str,
+struct Field {
+    title: &'static str,
     value: String,
     short: bool,
 }
 
 impl WebhookClient {
-    pub fn new(url: Option<String>) -> Self {
+    pub fn new(url: Option<String>, auth: &WebhookAuthConfig) -> Self {
         Self {
             client: reqwest::Client::builder()
                 .timeout(Duration::from_secs(10))
                 .build()
                 .unwrap(),
             url,
+            auth: RequestAuth::new(auth),
         }
     }
 
-    pub async fn send_alert(
-        &self,
-        chain: &str,
-        client_id: &str,
-        counterparty: Option<&str>,
-        status: &Status,
-    ) -> Result<()> {
+    pub async fn send_alert(&self, alert: &Alert) -> Result<()> {
         let Some(url) = &self.url else { return Ok(()) };
 
-        let (emoji, label, color, desc) = match status {
-            Status::Healthy { hours_until_expiry: h } => 
-                ("✅", "healthy", "good", format!("{:.1}h left", h)),
-            Status::Warning { hours_until_expiry: h } => 
-                ("⚠️", "warning", "warning", format!("{:.1}h left", h)),
-            Status::Critical { hours_until_expiry: h } => 
-                ("🚨", "critical", "danger", format!("{:.1}h left", h)),
-            Status::Expired { hours_since_expiry: h } => 
-                ("❌", "expired", "danger", format!("{:.1}h ago", h)),
-            Status::Error { reason } => 
-                ("❗", "error", "danger", reason.clone()),
+        let (chain, client_id, status) = (&alert.client.chain_id, &alert.client.client_id, &alert.client.status);
+        let label = status.label();
+        let color = match (status, &alert.kind) {
+            (_, AlertKind::Recovery { .. }) | (Status::Healthy { .. }, _) => "good",
+            (Status::Warning { .. }, _) => "warning",
+            _ => "danger",
         };
 
         let msg = SlackMessage {
-            text: format!("{} ibc alert: {}", emoji, label),
+            text: alert.title(),
             attachments: [Attachment {
                 color,
-                fields: [
-                    Field { title: "chain", value: chain.into(), short: true },
-                    Field { title: "client", value: client_id.into(), short: true },
-                    Field { title: "counterparty", value: counterparty.unwrap_or("?").into(), short: true },
-                    Field { title: "status", value: format!("{} - {}", label, desc), short: true },
-                ],
+                fields: alert
+                    .fields()
+                    .into_iter()
+                    .map(|(title, value)| Field { title, value, short: true })
+                    .collect(),
                 footer: "ibc-monitor",
                 ts: chrono::Utc::now().timestamp(),
             }],
@@ -80,10 +78,26 @@ impl WebhookClient {
 
         tracing::info!(%chain, %client_id, %label, "webhook");
 
-        let res = self.client.post(url).json(&msg).send().await?;
+        self.post(url, &msg).await
+    }
+
+    pub async fn send_message(&self, msg: &Message) -> Result<()> {
+        let Some(url) = &self.url else { return Ok(()) };
+        self.post(url, &SlackText { text: format!("*{}*\n```\n{}\n```", msg.title, msg.text) }).await
+    }
+
+    async fn post<T: Serialize>(&self, url: &str, body: &T) -> Result<()> {
+        let body = serde_json::to_vec(body)?;
+        let res = self
+            .auth
+            .apply(self.client.post(url), &body)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await?;
         if !res.status().is_success() {
             return Err(anyhow!("webhook {}: {}", res.status(), res.text().await?));
         }
         Ok(())
     }
-}
\ No newline at end of file
+}